@@ -0,0 +1,169 @@
+/***********************************************************************
+* simple-tokio/src/console.rs
+*   Plain terminal client for the 503 console teleprinter. Lines typed
+*   on stdin are sent to the teleprinter keyboard, and teleprinter
+*   output is written to stdout. In monitor mode, lines are instead
+*   sent as commands to the server's debugging monitor.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Add monitor mode for debug connections.
+***********************************************************************/
+
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::sync::{Arc, mpsc, atomic::{AtomicBool, Ordering}};
+use std::time::Duration;
+use bincode::{serialize, deserialize};
+
+use crate::message_frame::{MessageSocket, MessageSender, MessageReceiver};
+
+const STATUS_PERIOD: u64 = 100;         // msec
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+enum Event {
+    ShutDown,
+    Kill,
+    RequestStatus,
+    KeyIn(String),
+    Monitor(String)
+}
+
+fn event_sender(event_rx: mpsc::Receiver<Event>, mut sender: MessageSender) -> Result<()> {
+    /* Frame and send an event message to the core server */
+    use Event::*;
+
+    for ev in event_rx {
+        match ev {
+            RequestStatus => {
+                sender.send_sync("STAT", &Vec::new())?;
+            }
+            KeyIn(text) => {
+                sender.send_sync("KEYIN", &serialize(&text)?)?;
+            }
+            Monitor(line) => {
+                sender.send_sync("MONTR", &serialize(&line)?)?;
+            }
+            ShutDown => {
+                sender.send_sync("SHUT", &Vec::new())?;
+                break;
+            }
+            Kill => {
+                break;
+            }
+        };
+    }
+
+    Ok(())
+}
+
+fn core_receiver(mut receiver: MessageReceiver, event_tx: mpsc::Sender<Event>,
+                exit_flag: Arc<AtomicBool>) -> Result<()> {
+    /* Receive messages from the core server, printing teleprinter output
+    and ignoring the panel status */
+
+    let mut buf = vec![0_u8; 256];
+    let stdout = io::stdout();
+
+    loop {
+        match receiver.receive_sync(&mut buf) {
+            Err(e) => {
+                println!("console_receiver error: {}", e);
+                break;
+            }
+            Ok((id, code, payload)) => {
+                match std::str::from_utf8(code) {
+                    Ok("TYPE") | Ok("MONTR") => {
+                        let text: String = deserialize(payload)?;
+                        let mut out = stdout.lock();
+                        out.write_all(text.as_bytes())?;
+                        out.flush()?;
+                    }
+                    Ok("KILL") => {
+                        println!("Received KILL from Server {}", String::from_utf8_lossy(id));
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        println!("console_receiver corrupt message code {:?} -- {}", code, e)
+                    }
+                }
+            }
+        }
+    }
+
+    exit_flag.store(true, Ordering::Relaxed);
+    event_tx.send(Event::Kill).ok();
+    Ok(())
+}
+
+pub fn main(server_addr: &str) -> Result<()> {
+    run(server_addr, false)
+}
+
+pub fn monitor(server_addr: &str) -> Result<()> {
+    run(server_addr, true)
+}
+
+fn run(server_addr: &str, monitor: bool) -> Result<()> {
+    /* Connects to the server and relays stdin, either to the teleprinter
+    keyboard or to the debugging monitor */
+
+    let (event_tx, event_rx) = mpsc::channel::<Event>();
+    let socket = MessageSocket::connect_sync(server_addr, "TC")
+            .unwrap_or_else(|_| panic!("Failed to connect to core server on {}", server_addr));
+    println!("Connected to {} on {}", socket.peer_addr().unwrap(), socket.local_addr().unwrap());
+
+    let sender = socket.sender();
+    let receiver = socket.receiver();
+    let exit_flag = Arc::new(AtomicBool::new(false));
+
+    let event_tx_dup = event_tx.clone();
+    let exit_flag_dup = exit_flag.clone();
+    let core_thread = thread::spawn(move || {
+        core_receiver(receiver, event_tx_dup, exit_flag_dup)
+    });
+
+    let ev_thread = thread::spawn(move || {
+        event_sender(event_rx, sender)
+    });
+
+    // Poll the server for teleprinter output
+    if !monitor {
+        let status_tx = event_tx.clone();
+        let status_flag = exit_flag.clone();
+        thread::spawn(move || {
+            while !status_flag.load(Ordering::Relaxed) {
+                if status_tx.send(Event::RequestStatus).is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(STATUS_PERIOD));
+            }
+        });
+    }
+
+    // Send each line typed on stdin to the teleprinter keyboard or monitor
+    for line in io::stdin().lock().lines() {
+        if exit_flag.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let mut text = line?;
+        if monitor {
+            event_tx.send(Event::Monitor(text))?;
+        } else {
+            text.push('\n');
+            event_tx.send(Event::KeyIn(text))?;
+        }
+    }
+
+    event_tx.send(Event::ShutDown).ok();
+    ev_thread.join().unwrap()?;
+    core_thread.join().unwrap()?;
+    Ok(())
+}
@@ -9,23 +9,49 @@
 * Modification log.
 * 2021-01-24  P.Kimpel
 *   Original version, from simple-system/src/main.rs.
+* 2026-10-16  agent
+*   Add the console teleprinter terminal client.
+*   Add the telecode translation module.
+*   Add the headless run mode.
+*   Allow the server to start from a snapshot.
+*   Add the monitor client for debug connections.
+*   Add the trace listing.
+*   Add the disassembler.
+*   Let the run mode report its own errors, with their own exit status.
+*   Add the plotter command bits shared by the server and panel.
+*   Add the server's media and snapshot directory options.
 ***********************************************************************/
 
 pub mod panel;
 pub mod server;
+pub mod console;
 pub mod message_frame;
+pub mod telecode;
+pub mod plot_code;
 
 const DEFAULT_SOCKET: &str = "127.0.0.1:503";
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 fn main() -> Result<()> {
-    let mut args = std::env::args();
-    match (args.nth(1).as_ref().map(String::as_str), args.next().as_ref().map(String::as_str), args.next()) {
-        (Some("panel"), None, None) => panel::main(DEFAULT_SOCKET),
-        (Some("panel"), Some(addr), None) => panel::main(addr),
-        (Some("server"), None, None) => server::main(DEFAULT_SOCKET),
-        (Some("server"), Some(addr), None) => server::main(addr),
-        _ => Err("Usage: simple-tokio panel|server [socket-addr]".into()),
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["panel"] => panel::main(DEFAULT_SOCKET),
+        ["panel", addr] => panel::main(addr),
+        ["server", options @ ..] => server::main(DEFAULT_SOCKET, options),
+        ["console"] => console::main(DEFAULT_SOCKET),
+        ["console", addr] => console::main(addr),
+        ["monitor"] => console::monitor(DEFAULT_SOCKET),
+        ["monitor", addr] => console::monitor(addr),
+        ["run", options @ ..] => std::process::exit(server::run(options)),
+        ["trace", rest @ ..] => server::list_trace(rest),
+        ["disasm", rest @ ..] => server::disasm(rest),
+        _ => Err(concat!("Usage: simple-tokio panel|console|monitor [socket-addr]\n",
+                         "       simple-tokio server [socket-addr] [--restore snapshot]\n",
+                         "                           [--media dir] [--snapshots dir]\n",
+                         "       simple-tokio run [options] program-tape...\n",
+                         "       simple-tokio trace trace-file [first-last]\n",
+                         "       simple-tokio disasm word... | --snapshot file [first-last]").into()),
     }
 }
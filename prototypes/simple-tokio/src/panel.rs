@@ -9,16 +9,26 @@
 * Modification log.
 * 2021-01-24  P.Kimpel
 *   Original version, from simple-system/src/panel.rs.
+* 2026-10-16  agent
+*   Add the console teleprinter window.
+*   Add the plotter window with jog and export buttons.
+*   Add the device status window.
+*   Add the speed window to select and show the processor pacing.
+*   Add the snapshot window to save and restore the machine state.
+*   Take the plotter command bits from the server's plotter module.
+*   Take them from the shared plot_code module instead.
 ***********************************************************************/
 
+use std::collections::BTreeMap;
 use std::thread;
 use std::sync::{Arc, mpsc, Mutex, atomic::{AtomicBool, Ordering}};
 use bincode::{serialize, deserialize};
 
 //use chrono::{DateTime, Local, Timelike};
-use imgui::{im_str, Condition, StyleColor, StyleVar, Window, Ui};
+use imgui::{im_str, Condition, ImString, StyleColor, StyleVar, Window, Ui};
 
 use crate::message_frame::{MessageSocket, MessageSender, MessageReceiver};
+use crate::plot_code::{MINUS_X, MINUS_Y, PEN_DOWN, PEN_UP, PLUS_X, PLUS_Y};
 
 mod system_support;
 use system_support::{System};
@@ -31,6 +41,10 @@ use widgets::panel_lamp::PanelLamp;
 use widgets::register_display::RegisterDisplay;
 
 const STATUS_PERIOD: f64 = 1.0/20.0;    // sec
+const CONSOLE_LINES: usize = 24;        // teleprinter lines kept in the console window
+const PLOTTER_SVG_FILE: &str = "plot.svg";
+const PLOTTER_PNG_FILE: &str = "plot.png";
+const SNAPSHOT_FILE: &str = "503.snap";
 
 pub const FRAME_START: [u8;2] = [0x5A, 0x5A];
 pub const FRAME_END: [u8;2] = [0xA5, 0xA5];
@@ -56,7 +70,14 @@ pub struct PanelState {
     pub tag_glow: f32,
     pub type_hold_glow: f32,
     pub cbs_parity_glow: f32,
-    pub a_glow: Vec<f32>
+    pub a_glow: Vec<f32>,
+    // console teleprinter
+    pub console_text: String,
+    pub console_input: ImString,
+    // peripheral device status and activity lamp, by device name
+    pub devices: BTreeMap<String, (u64, f32)>,
+    // emulated/host time ratio achieved by the server
+    pub speed_ratio: f64
 }
 
 enum Event {
@@ -69,7 +90,12 @@ enum Event {
     Clear,
     Manual(bool),
     Reset,
-    PlotterManual(bool)
+    PlotterManual(bool),
+    PlotterJog(u8),
+    PlotterExport(&'static str, &'static str),
+    Speed(f64),
+    Snapshot(&'static str, &'static str),
+    KeyIn(String)
 }
 
 // UI Panel A Definitions
@@ -359,7 +385,7 @@ impl<'a> PanelC<'a> {
             .title_bar(false)
             .scrollable(false)
             .position([10.0, 200.0], Condition::FirstUseEver)
-            .size([520.0, 30.0], Condition::FirstUseEver);
+            .size([600.0, 30.0], Condition::FirstUseEver);
 
         // Build our Panel C window and its inner widgets in the closure
         panel.build(&ui, || {
@@ -369,6 +395,163 @@ impl<'a> PanelC<'a> {
     }
 }
 
+// Console Teleprinter Window Definitions
+
+struct ConsoleWindow {
+}
+
+impl ConsoleWindow {
+    fn build(&self, ui: &Ui, state: &mut PanelState, event_tx: &mpsc::Sender<Event>) {
+        // Create the console teleprinter window
+
+        let window = Window::new(im_str!("Console"))
+            .resizable(true)
+            .collapsible(true)
+            .position([10.0, 240.0], Condition::FirstUseEver)
+            .size([600.0, 400.0], Condition::FirstUseEver);
+
+        window.build(&ui, || {
+            ui.text(&state.console_text);
+            ui.separator();
+            if ui.input_text(im_str!("Keyboard"), &mut state.console_input)
+                    .enter_returns_true(true)
+                    .build() && state.power_on {
+                let mut text = state.console_input.to_str().to_string();
+                text.push('\n');
+                event_tx.send(Event::KeyIn(text)).unwrap();
+                state.console_input.clear();
+            }
+        });
+    }
+}
+
+// Plotter Window Definitions
+
+struct PlotterWindow {
+}
+
+impl PlotterWindow {
+    fn build(&self, ui: &Ui, state: &PanelState, event_tx: &mpsc::Sender<Event>) {
+        // Create the plotter window. The pen can be jogged only while
+        // PLOTTER MANUAL is on
+
+        let window = Window::new(im_str!("Plotter"))
+            .resizable(false)
+            .collapsible(true)
+            .position([620.0, 10.0], Condition::FirstUseEver)
+            .size([170.0, 150.0], Condition::FirstUseEver);
+
+        window.build(&ui, || {
+            let jogs = [
+                (im_str!("-X"), MINUS_X), (im_str!("+X"), PLUS_X),
+                (im_str!("-Y"), MINUS_Y), (im_str!("+Y"), PLUS_Y),
+                (im_str!("Up"), PEN_UP), (im_str!("Down"), PEN_DOWN)];
+            for (x, (label, bits)) in jogs.iter().enumerate() {
+                if x % 2 == 1 {
+                    ui.same_line(0.0);
+                }
+                if ui.button(label, [60.0, 20.0]) && state.plotter_manual {
+                    event_tx.send(Event::PlotterJog(*bits)).unwrap();
+                }
+            }
+
+            ui.separator();
+            if ui.button(im_str!("SVG"), [60.0, 20.0]) && state.power_on {
+                event_tx.send(Event::PlotterExport("PLSVG", PLOTTER_SVG_FILE)).unwrap();
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("PNG"), [60.0, 20.0]) && state.power_on {
+                event_tx.send(Event::PlotterExport("PLPNG", PLOTTER_PNG_FILE)).unwrap();
+            }
+        });
+    }
+}
+
+// Device Status Window Definitions
+
+struct DeviceWindow {
+}
+
+impl DeviceWindow {
+    fn build(&self, ui: &Ui, state: &PanelState) {
+        // Create the window listing each peripheral's status word
+
+        let window = Window::new(im_str!("Devices"))
+            .resizable(true)
+            .collapsible(true)
+            .position([620.0, 170.0], Condition::FirstUseEver)
+            .size([170.0, 160.0], Condition::FirstUseEver);
+
+        window.build(&ui, || {
+            for (name, (status, glow)) in state.devices.iter() {
+                let mark = if *glow > 0.5 {'*'} else {' '};
+                ui.text(format!("{}{:<11}{:o}", mark, name, status));
+            }
+        });
+    }
+}
+
+// Speed Window Definitions
+
+struct SpeedWindow {
+}
+
+impl SpeedWindow {
+    fn build(&self, ui: &Ui, state: &PanelState, event_tx: &mpsc::Sender<Event>) {
+        // Create the window that selects the processor speed. A ratio of
+        // zero runs the processor unthrottled
+
+        let window = Window::new(im_str!("Speed"))
+            .resizable(false)
+            .collapsible(true)
+            .position([620.0, 340.0], Condition::FirstUseEver)
+            .size([170.0, 70.0], Condition::FirstUseEver);
+
+        window.build(&ui, || {
+            let speeds = [(im_str!("1x"), 1.0), (im_str!("10x"), 10.0), (im_str!("Max"), 0.0)];
+            for (x, (label, ratio)) in speeds.iter().enumerate() {
+                if x > 0 {
+                    ui.same_line(0.0);
+                }
+                if ui.button(label, [45.0, 20.0]) {
+                    event_tx.send(Event::Speed(*ratio)).unwrap();
+                }
+            }
+
+            ui.text(format!("Achieved x{:.2}", state.speed_ratio));
+        });
+    }
+}
+
+// Snapshot Window Definitions
+
+struct SnapshotWindow {
+}
+
+impl SnapshotWindow {
+    fn build(&self, ui: &Ui, event_tx: &mpsc::Sender<Event>) {
+        // Create the window that saves and restores the machine state
+
+        let window = Window::new(im_str!("Snapshot"))
+            .resizable(false)
+            .collapsible(true)
+            .position([620.0, 420.0], Condition::FirstUseEver)
+            .size([170.0, 70.0], Condition::FirstUseEver);
+
+        window.build(&ui, || {
+            if ui.button(im_str!("Save"), [70.0, 20.0]) {
+                event_tx.send(Event::Snapshot("SNSAV", SNAPSHOT_FILE)).unwrap();
+            }
+            ui.same_line(0.0);
+            if ui.button(im_str!("Restore"), [70.0, 20.0]) {
+                event_tx.send(Event::Snapshot("SNRST", SNAPSHOT_FILE)).unwrap();
+            }
+
+            ui.text(SNAPSHOT_FILE);
+        });
+    }
+}
+
 // Thread functions
 
 fn event_sender(event_rx: mpsc::Receiver<Event>, mut sender: MessageSender) -> Result<()> {
@@ -402,6 +585,18 @@ fn event_sender(event_rx: mpsc::Receiver<Event>, mut sender: MessageSender) -> R
             PlotterManual(state) => {
                 sender.send_sync("PLTMN", &serialize(&state)?)?;
             }
+            PlotterJog(bits) => {
+                sender.send_sync("PLJOG", &serialize(&bits)?)?;
+            }
+            PlotterExport(code, path) | Snapshot(code, path) => {
+                sender.send_sync(code, &serialize(path)?)?;
+            }
+            Speed(ratio) => {
+                sender.send_sync("SPEED", &serialize(&ratio)?)?;
+            }
+            KeyIn(text) => {
+                sender.send_sync("KEYIN", &serialize(&text)?)?;
+            }
             ShutDown => {
                 sender.send_sync("SHUT", &Vec::new())?;
                 break;
@@ -496,6 +691,23 @@ fn core_receiver(mut receiver: MessageReceiver, event_tx: mpsc::Sender<Event>,
                             state.next_status_clock = state.clock;
                         }
                     }
+                    Ok("TYPE") => {
+                        let text: String = deserialize(payload)?;
+                        state.console_text.push_str(&text);
+                        let lines = state.console_text.matches('\n').count();
+                        if lines > CONSOLE_LINES {
+                            let excess = state.console_text.match_indices('\n')
+                                    .nth(lines - CONSOLE_LINES - 1).map(|(x, _)| x + 1).unwrap_or(0);
+                            state.console_text.drain(..excess);
+                        }
+                    }
+                    Ok("RATIO") => {
+                        state.speed_ratio = deserialize(payload)?;
+                    }
+                    Ok("DEVST") => {
+                        let (name, status, glow): (String, u64, f32) = deserialize(payload)?;
+                        state.devices.insert(name, (status, glow));
+                    }
                     Ok("ESTAT") => {
                         //println!("Received Server status");
                         if state.status_request_count > 0 {
@@ -530,6 +742,11 @@ pub fn main(server_addr: &str) -> Result<()> {
     let panel_a = PanelA::define();
     let panel_b = PanelB::define();
     let panel_c = PanelC::define();
+    let console = ConsoleWindow {};
+    let plotter = PlotterWindow {};
+    let devices = DeviceWindow {};
+    let speed = SpeedWindow {};
+    let snapshot = SnapshotWindow {};
 
     // Create the internal panel-state structure
 
@@ -550,7 +767,11 @@ pub fn main(server_addr: &str) -> Result<()> {
         tag_glow: 0.0,
         type_hold_glow: 0.0,
         cbs_parity_glow: 0.0,
-        a_glow: vec![0.0_f32]
+        a_glow: vec![0.0_f32],
+        console_text: String::new(),
+        console_input: ImString::with_capacity(256),
+        devices: BTreeMap::new(),
+        speed_ratio: 0.0
     }));
 
     // Create the internal event channel and TCP connection
@@ -619,6 +840,11 @@ pub fn main(server_addr: &str) -> Result<()> {
         panel_a.build(&ui, &state, &event_tx);
         panel_b.build(&ui, &state, &event_tx);
         panel_c.build(&ui, &state, &event_tx);
+        console.build(&ui, &mut state, &event_tx);
+        plotter.build(&ui, &state, &event_tx);
+        devices.build(&ui, &state);
+        speed.build(&ui, &state, &event_tx);
+        snapshot.build(&ui, &event_tx);
 
         // Check if it's time for the next server status request
         if state.power_on {
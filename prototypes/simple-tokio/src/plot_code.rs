@@ -0,0 +1,24 @@
+/***********************************************************************
+* simple-tokio/src/plot_code.rs
+*   Command bits of the Elliott 503 incremental digital plotter, shared
+*   by the server's plotter and the panel's jog buttons.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Each command moves the pen one step in X and/or Y, or raises or
+* lowers the pen. Command bits:
+*       1  +X       2  -X       4  +Y       8  -Y
+*       16 pen up   32 pen down
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version, from server/plotter.rs.
+***********************************************************************/
+
+pub const PLUS_X: u8 = 1;
+pub const MINUS_X: u8 = 2;
+pub const PLUS_Y: u8 = 4;
+pub const MINUS_Y: u8 = 8;
+pub const PEN_UP: u8 = 16;
+pub const PEN_DOWN: u8 = 32;
@@ -9,9 +9,39 @@
 * Modification log.
 * 2021-01-24  P.Kimpel
 *   Original version, from simple-system/src/server.rs.
+* 2026-10-16  agent
+*   Replace simple_cpu counting loop with the 503 processor.
+*   Add the core store.
+*   Implement INIT with the Initial Instructions loader.
+*   Add the core backing store and CBS PARITY lamp.
+*   Add store protection controlled by NO PROTN.
+*   Add the paper tape reader.
+*   Add the paper tape punch.
+*   Add the console teleprinter and allow several clients to attach.
+*   Add the digital plotter, taken offline by PLOTTER MANUAL.
+*   Add the line printer.
+*   Add the magnetic tape controller and handlers.
+*   Attach peripherals to an I/O bus through the Device trait.
+*   Drive BUSY from peripheral waits and skip time across them.
+*   Time each instruction from the processor's timing table.
+*   Pace the processor against host time at a selectable speed.
+*   Add the headless batch-run mode.
+*   Save and restore machine snapshots.
+*   Add the debugging monitor on stdin and debug connections.
+*   Add the instruction trace.
+*   Add the disassembler.
+*   Preload programs from tape images on the host.
+*   Add the exact floating-point unit.
+*   Make the plotter module public, for the panel's jog buttons.
+*   Send teleprinter output to clients in frame-sized pieces.
+*   Test the skip across device waits.
+*   Take the file names sent by clients within the media and snapshot
+*   directories, chosen with --media and --snapshots.
 ***********************************************************************/
 
 
+use std::io::{self, BufRead};
+use std::path::PathBuf;
 use std::thread;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -23,12 +53,76 @@ use ctrlc;
 use crate::message_frame::{MessageListener, MessageSender, MessageReceiver};
 
 mod register;
-use register::{Register, FlipFlop, EmulationClock};
+use register::{FlipFlop, EmulationClock};
+
+mod store;
+use store::Store;
+
+mod device;
+use device::IoBus;
+
+mod backing_store;
+use backing_store::BackingStore;
+
+mod tape_reader;
+use tape_reader::TapeReader;
+
+mod tape_punch;
+use tape_punch::TapePunch;
+
+mod typewriter;
+use typewriter::{PaperMark, Typewriter};
+
+mod plotter;
+use plotter::Plotter;
+
+mod line_printer;
+use line_printer::LinePrinter;
+
+mod magnetic_tape;
+use magnetic_tape::MagneticTape;
+
+mod timing;
+mod pacing;
+use pacing::{Pacer, Speed};
+
+mod processor;
+mod initial_instructions;
+use processor::{Processor, Step, StopReason};
+
+mod snapshot;
+
+mod monitor;
+use monitor::Monitor;
+
+mod loader;
+mod floating;
+
+mod disasm;
+pub use disasm::main as disasm;
+
+mod trace;
+use trace::Tracer;
+pub use trace::main as list_trace;
+
+mod batch;
+pub use batch::main as run;
+
+mod media;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 const SERVER_TIMEOUT: u64 = 5;          // sec
-const TIMER_PERIOD: f64 = 7.2e-6;       // sec
+const SLICE_STEPS: usize = 500;         // instructions between pacing checks
+const IDLE_PAUSE: u64 = 10;             // msec host sleep while the processor is idle
+const STORE_MODULES: usize = 1;         // 8192-word modules
+const CBS_UNIT: u64 = 0;
+const READER_UNIT: u64 = 0;
+const PUNCH_UNIT: u64 = 1;
+const TYPEWRITER_UNIT: u64 = 2;
+const PLOTTER_UNIT: u64 = 3;
+const PRINTER_UNIT: u64 = 4;
+const MTAPE_UNIT: u64 = 5;            // control, status and block transfers
 
 pub struct ServerState {
     pub last_clock: f64,
@@ -47,9 +141,20 @@ pub struct ServerState {
     pub tag_glow: f32,
     pub type_hold_glow: f32,
     pub bs_parity_glow: f32,
+    // real-time pacing
+    pub pacer: Pacer,
+    pub speed_ratio: f64,               // achieved emulated/host time ratio
+    pub last_stop: Option<StopReason>,  // why the processor last stopped
+    pub monitor: Monitor,
+    pub tracer: Option<Tracer>,         // instruction trace, if one is being written
+    // host directories for the files clients name
+    pub media_dir: PathBuf,             // tapes, listings, plots and traces
+    pub snapshot_dir: PathBuf,
     // Registers & Flip-flops
     pub busy_ff : FlipFlop,
-    pub a_reg: Register<u32>
+    pub processor: Processor,
+    pub store: Store,
+    pub bus: IoBus
 }
 
 fn send_status(sender: &mut MessageSender, state: &ServerState) -> Result<()> {
@@ -66,16 +171,34 @@ fn send_status(sender: &mut MessageSender, state: &ServerState) -> Result<()> {
     sender.send_sync("THOLD", &serialize(&state.type_hold_glow)?)?;
     sender.send_sync("BSPAR", &serialize(&state.bs_parity_glow)?)?;
     sender.send_sync("BUSY", &serialize(&state.busy_ff.read_glow())?)?;
-    sender.send_sync("A", &serialize(&state.a_reg.read_glow())?)?;
+    sender.send_sync("RATIO", &serialize(&state.speed_ratio)?)?;
+    sender.send_sync("A", &serialize(&state.processor.a_reg.read_glow())?)?;
+    for device in state.bus.devices() {
+        sender.send_sync("DEVST", &serialize(&(device.name(), device.status(), device.glow()))?)?;
+    }
     sender.send_sync("ESTAT", &Vec::new())?;
     Ok(())
 }
 
+fn send_typewriter(sender: &mut MessageSender, state: &ServerState, printed: &mut PaperMark) -> Result<()> {
+    /* Sends the teleprinter output this client has not yet seen */
+
+    if let Some(typewriter) = state.bus.get::<Typewriter>() {
+        // A new client is sent all the paper, which can outgrow one frame
+        for piece in Typewriter::frames(typewriter.printed_since(printed)) {
+            sender.send_sync("TYPE", &serialize(piece)?)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn panel_receiver(mut receiver: MessageReceiver, mut sender: MessageSender,
         run_flag: Arc<AtomicBool>, state: Arc<Mutex<ServerState>>) -> Result<()> {
 
     let mut buf = vec![0_u8; 256];
     let mut running = true;
+    let mut printed = PaperMark::default(); // teleprinter output sent to this client
 
     while running {
         match receiver.receive_sync(&mut buf) {
@@ -111,16 +234,23 @@ fn panel_receiver(mut receiver: MessageReceiver, mut sender: MessageSender,
                             }
                         }
                         send_status(&mut sender, &state).expect("receiver error sending status");
+                        send_typewriter(&mut sender, &state, &mut printed)
+                                .expect("receiver error sending typewriter output");
                     }
                     Ok("INIT") => {
                         println!("receiver INIT from {}", String::from_utf8_lossy(id));
+                        initial_instructions(&mut state);
                     }
                     Ok("CLEAR") => {
                         println!("receiver CLEAR");
-                        state.a_reg.set(0);
+                        state.processor.clear();
+                        state.bus.clear();
                     }
                     Ok("RESET") => {
                         println!("receiver RESET");
+                        state.error_glow = 0.0;
+                        state.bs_parity_glow = 0.0;
+                        state.bus.reset();
                         state.reset_state = true;
                         state.reset_countdown = 15;
                     }
@@ -128,28 +258,80 @@ fn panel_receiver(mut receiver: MessageReceiver, mut sender: MessageSender,
                         let on_off = deserialize(payload)?;
                         println!("receiver MANL {}", on_off);
                         state.manual_state = on_off;
+                        state.processor.running = !on_off;
                     }
                     Ok("PLTMN") => {
                         let on_off = deserialize(payload)?;
                         println!("receiver PLTMN {}", on_off);
                         state.plotter_manual = on_off;
+                        if let Some(plotter) = state.bus.get_mut::<Plotter>() {
+                            plotter.manual = on_off;
+                        }
                     }
                     Ok("NOPRO") => {
                         let on_off = deserialize(payload)?;
                         println!("receiver NOPRO {}", on_off);
                         state.no_protn = on_off;
+                        state.store.set_protection(!on_off);
                     }
                     Ok("POWER") => {
                         let on_off = deserialize(payload)?;
                         println!("receiver POWER {}", on_off);
                         change_power(&mut sender, &mut state, &on_off);
                     }
+                    Ok("SPEED") => {
+                        let ratio: f64 = deserialize(payload)?;
+                        println!("receiver SPEED {}", ratio);
+                        let speed = if ratio > 0.0 {Speed::Ratio(ratio)} else {Speed::Unthrottled};
+                        state.pacer.set_speed(speed);
+                    }
+                    Ok("PROT") => {
+                        let (first, last): (u64, u64) = deserialize(payload)?;
+                        println!("receiver PROT {}-{}", first, last);
+                        state.store.protect(first, last);
+                    }
+                    Ok("UNPRO") => {
+                        println!("receiver UNPRO");
+                        state.store.unprotect_all();
+                    }
+                    Ok("MONTR") => {
+                        let line: String = deserialize(payload)?;
+                        let output = monitor::command(&mut state, &line);
+                        let reply = monitor::take_notices(&mut state) + &output;
+                        sender.send_sync("MONTR", &serialize(&reply)?)?;
+                    }
+                    Ok("SNSAV") => {
+                        let name: String = deserialize(payload)?;
+                        println!("receiver SNSAV {}", name);
+                        let saved = media::resolve(&state.snapshot_dir, &name)
+                                .map_err(Into::into)
+                                .and_then(|path| snapshot::save(&state, path));
+                        if let Err(e) = saved {
+                            println!("receiver SNSAV failed: {}", e);
+                        }
+                    }
+                    Ok("SNRST") => {
+                        let name: String = deserialize(payload)?;
+                        println!("receiver SNRST {}", name);
+                        let restored = media::resolve(&state.snapshot_dir, &name)
+                                .map_err(Into::into)
+                                .and_then(|path| snapshot::restore(&mut state, path));
+                        if let Err(e) = restored {
+                            println!("receiver SNRST failed: {}", e);
+                        }
+                    }
                     Ok("SHUT") => {
                         running = false;
                         println!("receiver SHUT from {}", String::from_utf8_lossy(id));
                     }
-                    Ok(bad_code) => {
-                        println!("receiver unrecognized message code {}", bad_code);
+                    Ok(code) => {
+                        // Anything else is a message for one of the devices
+                        let state = &mut *state;
+                        match state.bus.command(code, payload, &state.media_dir) {
+                            Some(Ok(())) => {}
+                            Some(Err(e)) => println!("receiver {} failed: {}", code, e),
+                            None => println!("receiver unrecognized message code {}", code)
+                        }
                     }
                     Err(e) => {
                         println!("receiver corrupt message code {:?} -- {}", code, e);
@@ -175,6 +357,7 @@ fn change_power(mut sender: &mut MessageSender, state: &mut ServerState, on_off:
         state.manual_state = false;
         state.plotter_manual = false;
         state.no_protn = false;
+        state.store.set_protection(true);
         state.reset_state = false;
         state.transfer_glow = 0.0;
         state.air_cond_glow = 0.0;
@@ -183,20 +366,114 @@ fn change_power(mut sender: &mut MessageSender, state: &mut ServerState, on_off:
         state.type_hold_glow = 0.0;
         state.bs_parity_glow = 0.0;
         state.busy_ff.set(false);
-        if *on_off {
-            state.a_reg.add(7654321);
-        } else {
-            state.a_reg.set(0);
-        }
+        state.processor.clear();
+        state.processor.running = *on_off;
+        state.bus.power(*on_off);
 
         state.eclock.advance(1e6);
-        state.a_reg.update_glow(1.0);
+        state.processor.a_reg.update_glow(1.0);
         state.busy_ff.update_glow(1.0);
+        state.pacer.restart();
 
         send_status(&mut sender, &state).expect("Error sending power status");
     }
 }
 
+fn initial_instructions(state: &mut ServerState) {
+    /* Loads the Initial Instructions into low store and starts the
+    processor at location 0. The loader then reads a program from the
+    paper tape reader */
+
+    if state.power_on {
+        initial_instructions::load(&mut state.store).expect("Error loading Initial Instructions");
+        state.processor.clear();
+        state.processor.running = !state.manual_state;
+        state.last_stop = None;
+    }
+}
+
+fn record_trace(st: &mut ServerState) {
+    /* Writes the instruction just executed to the trace, if one is on */
+
+    if let (Some(tracer), Some(executed)) = (st.tracer.as_mut(), st.processor.executed) {
+        if let Err(e) = tracer.record(st.eclock.read(), executed, &st.processor) {
+            println!("trace write error, trace stopped: {}", e);
+            st.tracer = None;
+        }
+    }
+}
+
+fn run_slice(st: &mut ServerState) -> bool {
+    /* Runs the processor for up to SLICE_STEPS instructions, skipping
+    emulated time across waits for devices that will become ready on
+    their own. Returns true if the processor is idle: stopped, or waiting
+    on something outside the machine */
+
+    let mut idle = false;
+    for _ in 0..SLICE_STEPS {
+        if !st.processor.running {
+            idle = true;
+            break;
+        }
+
+        if st.monitor.at_breakpoint(&st.processor) {
+            st.processor.running = false;
+            idle = true;
+            break;
+        }
+
+        let addr = st.processor.scr.read();
+        let step = st.processor.step(&mut st.store, &mut st.bus);
+        record_trace(st);
+        match step {
+            Step::Continue => {
+                if st.busy_ff.read() {
+                    st.busy_ff.update_glow(0.0);
+                    st.busy_ff.set(false);
+                }
+
+                if st.monitor.executed(addr, &st.store) {
+                    st.processor.running = false;
+                }
+            }
+            Step::Wait => {
+                // Stall on the busy device. If it will become ready
+                // on its own, skip emulated time to that point;
+                // otherwise let time pass with the host
+                st.busy_ff.set(true);
+                match st.bus.ready_time() {
+                    Some(ready) => {
+                        let now = st.eclock.read();
+                        if ready > now {
+                            st.eclock.advance(ready - now);
+                        }
+                        st.busy_ff.update_glow(0.0);
+                    }
+                    None => {
+                        idle = true;
+                        break;
+                    }
+                }
+            }
+            Step::Stop(reason) => {
+                println!("processor stopped {:?}", reason);
+                st.last_stop = Some(reason);
+                if !matches!(reason, StopReason::DynamicStop(_)) {
+                    st.error_glow = 1.0;
+                }
+
+                if let StopReason::Parity(_) = reason {
+                    st.bs_parity_glow = 1.0;
+                }
+            }
+        }
+
+        st.bus.update_lamps();
+    }
+
+    idle
+}
+
 fn simple_cpu(running: Arc<AtomicBool>, state: Arc<Mutex<ServerState>>) {
 
     loop {
@@ -208,23 +485,35 @@ fn simple_cpu(running: Arc<AtomicBool>, state: Arc<Mutex<ServerState>>) {
                 drop(state);
                 thread::sleep(Duration::from_secs(2));
             } else {
-                for _ in 0..500 {
-                    let count = state.a_reg.read();
-                    state.a_reg.add(1);
-                    state.busy_ff.set(count & 1 == 0);
-                    state.eclock.advance(TIMER_PERIOD);
+                let st = &mut *state;
+                let idle = run_slice(st);
+
+                if idle {
+                    st.pacer.idle();
+                    st.busy_ff.update_glow(0.0);
+                    st.bus.update_lamps();
                 }
 
+                st.transfer_glow = st.bus.transfer_glow();
+                st.type_hold_glow = st.bus.get::<Typewriter>().map_or(0.0, |t| *t.hold_ff.read_glow());
+                st.speed_ratio = st.pacer.achieved();
+                let delay = if idle {Duration::from_millis(IDLE_PAUSE)} else {st.pacer.delay()};
+
                 drop(state);
-                thread::sleep(Duration::from_millis((TIMER_PERIOD*1e6) as u64));
+                if delay > Duration::from_secs(0) {
+                    thread::sleep(delay);
+                } else {
+                    thread::yield_now();
+                }
             }
         }
     }
 }
 
-pub fn main(socket_addr: &str) -> Result<()> {
-    let eclock = Arc::new(EmulationClock::new(0.0));
+fn new_state(speed: Speed) -> ServerState {
+    /* Builds the machine with its peripherals attached, powered off */
 
+    let eclock = Arc::new(EmulationClock::new(0.0));
     let mut state = ServerState {
         last_clock: 0.0,
         eclock: eclock.clone(),
@@ -240,11 +529,62 @@ pub fn main(socket_addr: &str) -> Result<()> {
         tag_glow: 0.0,
         type_hold_glow: 0.0,
         bs_parity_glow: 0.0,
+        pacer: Pacer::new(eclock.clone(), speed),
+        speed_ratio: 0.0,
+        last_stop: None,
+        monitor: Monitor::new(),
+        tracer: None,
+        media_dir: PathBuf::from("."),
+        snapshot_dir: PathBuf::from("."),
         busy_ff: FlipFlop::new(eclock.clone()),
-        a_reg: Register::new(30, eclock.clone())
+        processor: Processor::new(eclock.clone()),
+        store: Store::new(STORE_MODULES, eclock.clone()),
+        bus: IoBus::new()
     };
 
-    state.a_reg.set(1234567);
+    // Attach the peripherals at their unit numbers
+    let bus = &mut state.bus;
+    bus.attach(&[], &[CBS_UNIT], Box::new(BackingStore::new(backing_store::DEFAULT_SIZE, eclock.clone())));
+    bus.attach(&[READER_UNIT], &[], Box::new(TapeReader::new(eclock.clone())));
+    bus.attach(&[PUNCH_UNIT], &[], Box::new(TapePunch::new(eclock.clone())));
+    bus.attach(&[TYPEWRITER_UNIT], &[], Box::new(Typewriter::new(eclock.clone())));
+    bus.attach(&[PLOTTER_UNIT], &[], Box::new(Plotter::new(eclock.clone())));
+    bus.attach(&[PRINTER_UNIT], &[], Box::new(LinePrinter::new(eclock.clone())));
+    bus.attach(&[MTAPE_UNIT], &[MTAPE_UNIT], Box::new(MagneticTape::new(eclock)));
+
+    state.processor.clear();
+    state
+}
+
+pub fn main(default_addr: &str, options: &[&str]) -> Result<()> {
+    /* Runs the server for panels and consoles. Options are an optional
+    socket address, then --restore snapshot, --media dir and --snapshots
+    dir. Any client that can connect can operate the machine, so the
+    default address is on the loopback interface */
+
+    let mut socket_addr = default_addr;
+    let mut options = options;
+    if let Some(addr) = options.first().filter(|arg| !arg.starts_with("--")) {
+        socket_addr = addr;
+        options = &options[1..];
+    }
+
+    let mut state = new_state(Speed::Ratio(1.0));
+    let mut restore = None;
+    for option in options.chunks(2) {
+        match *option {
+            ["--restore", path] => restore = Some(path),
+            ["--media", dir] => state.media_dir = PathBuf::from(dir),
+            ["--snapshots", dir] => state.snapshot_dir = PathBuf::from(dir),
+            _ => return Err(format!("unrecognized server option {}", option.join(" ")).into())
+        }
+    }
+
+    if let Some(path) = restore {
+        println!("Restoring snapshot {}", path);
+        snapshot::restore(&mut state, path)?;
+    }
+
     let state_ref = Arc::new(Mutex::new(state));
 
     // Set up a shared Boolean and Ctrl-C handler
@@ -260,6 +600,17 @@ pub fn main(socket_addr: &str) -> Result<()> {
     let listener = MessageListener::bind_sync(socket_addr, "MF")
                    .expect("Failed to bind TcpListener");
 
+    // Read monitor commands from stdin until it is closed
+    let state = state_ref.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) => print!("{}", monitor::command(&mut state.lock().unwrap(), &line)),
+                Err(_) => break
+            }
+        }
+    });
+
     // Spawn the simplistic processor
     let state = state_ref.clone();
     let run_flag = running.clone();
@@ -267,7 +618,9 @@ pub fn main(socket_addr: &str) -> Result<()> {
         simple_cpu(run_flag, state)
     });
 
-    // Get the next incoming TCP connection
+    // Get the next incoming TCP connection. Several clients (panels and
+    // consoles) may be attached at once
+    let mut receivers = Vec::new();
     while running.load(Ordering::Relaxed) {
         match listener.accept_sync(SERVER_TIMEOUT) {
             Some(r) => {
@@ -283,14 +636,9 @@ pub fn main(socket_addr: &str) -> Result<()> {
                         let run_flag = running.clone();
                         let r = socket.receiver();
                         let s = socket.sender();
-                        let receiver = thread::spawn(move || {
+                        receivers.push(thread::spawn(move || {
                             panel_receiver(r, s, run_flag, state)
-                        });
-
-                        match receiver.join() {
-                            Ok(_) => println!("server receiver thread terminated normally"),
-                            Err(e) => println!("server receiver thread error {:?}", e)
-                        }
+                        }));
                     }
                 }
             }
@@ -298,9 +646,41 @@ pub fn main(socket_addr: &str) -> Result<()> {
         }
     }
 
+    for receiver in receivers {
+        match receiver.join() {
+            Ok(_) => println!("server receiver thread terminated normally"),
+            Err(e) => println!("server receiver thread error {:?}", e)
+        }
+    }
+
     match cpu.join() {
         Ok(_) => println!("server CPU thread terminated normally"),
         Err(e) => println!("server CPU thread error {:?}", e)
     }
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use processor::{instruction, word};
+
+    #[test]
+    fn run_slice_skips_device_waits() {
+        // The second character waits on the reader; the slice skips
+        // emulated time to the reader's ready time and reaches the
+        // dynamic stop within one slice
+        let mut state = new_state(Speed::Unthrottled);
+        state.power_on = true;
+        state.store.poke(0, word(instruction(0o71, 0), false, instruction(0o71, 0))).unwrap();
+        state.store.poke(1, word(instruction(0o40, 1), false, instruction(0o40, 1))).unwrap();
+        state.bus.get_mut::<TapeReader>().unwrap().mount_bytes("test", vec![0o01, 0o02]);
+        state.processor.running = true;
+
+        run_slice(&mut state);
+        assert_eq!(state.last_stop, Some(StopReason::DynamicStop(1)));
+        assert_eq!(state.processor.a_reg.read(), 0o03);
+        assert!(state.eclock.read() >= tape_reader::CHAR_PERIOD);
+    }
+}
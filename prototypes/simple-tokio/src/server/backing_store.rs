@@ -0,0 +1,293 @@
+/***********************************************************************
+* simple-tokio/src/server/backing_store.rs
+*   Module "backing_store" for the 503 core backing store (CBS): a
+*   large secondary core store that exchanges blocks of words with the
+*   main store, with a parity digit kept for each word.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Each CBS word is held as 40 bits: the 39-bit word plus an odd-parity
+* digit in bit 39. Parity is generated when a word is written to the
+* CBS and checked when it is read back.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Implement the Device trait.
+*   Save and restore the device state in snapshots.
+*   Test parity failures on a block transfer.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use super::device::{CommandResult, Device};
+use super::processor::StopReason;
+use super::register::{EmulationClock, EmulationTick};
+use super::snapshot::{self, Snapshot, SnapshotResult};
+use super::store::{Address, Word, Store, StoreError, WORD_MASK};
+
+pub const DEFAULT_SIZE: usize = 65536;          // words
+pub const WORD_TIME: EmulationTick = 6.0e-6;     // sec per word transferred
+pub const START_TIME: EmulationTick = 36.0e-6;   // sec to start a transfer
+
+const PARITY_BIT: u64 = 1 << 39;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackingStoreError {
+    NonExistent(Address),                       // CBS address beyond installed size
+    Parity(Address),                            // parity failure reading CBS address
+    Store(StoreError)                           // main store address error
+}
+
+impl fmt::Display for BackingStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackingStoreError::NonExistent(addr) => write!(f, "non-existent CBS address {}", addr),
+            BackingStoreError::Parity(addr) => write!(f, "CBS parity failure at {}", addr),
+            BackingStoreError::Store(e) => e.fmt(f)
+        }
+    }
+}
+
+impl std::error::Error for BackingStoreError {}
+
+impl From<StoreError> for BackingStoreError {
+    fn from(e: StoreError) -> Self {
+        BackingStoreError::Store(e)
+    }
+}
+
+fn parity(word: Word) -> u64 {
+    /* Returns the parity digit that makes the count of one bits odd */
+    if word.count_ones() & 1 == 0 {PARITY_BIT} else {0}
+}
+
+
+/***** BackingStore *****/
+
+pub struct BackingStore {
+    clock: Arc<EmulationClock>,
+    words: Vec<u64>,
+    pub parity_error: bool
+}
+
+impl BackingStore {
+
+    pub fn new(size: usize, clock: Arc<EmulationClock>) -> Self {
+        /* Creates a CBS of the specified number of words, all zero with
+        good parity */
+
+        BackingStore {
+            clock,
+            words: vec![parity(0); size],
+            parity_error: false
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.words.len()
+    }
+
+    fn index(&self, addr: Address) -> Result<usize, BackingStoreError> {
+        if (addr as usize) < self.words.len() {
+            Ok(addr as usize)
+        } else {
+            Err(BackingStoreError::NonExistent(addr))
+        }
+    }
+
+    pub fn read_word(&self, addr: Address) -> Result<Word, BackingStoreError> {
+        /* Returns a word after checking its parity */
+
+        let stored = self.words[self.index(addr)?];
+        let word = stored & WORD_MASK;
+        if stored & PARITY_BIT == parity(word) {
+            Ok(word)
+        } else {
+            Err(BackingStoreError::Parity(addr))
+        }
+    }
+
+    pub fn write_word(&mut self, addr: Address, word: Word) -> Result<(), BackingStoreError> {
+        /* Stores a word with newly-generated parity */
+
+        let x = self.index(addr)?;
+        let word = word & WORD_MASK;
+        self.words[x] = word | parity(word);
+        Ok(())
+    }
+
+    pub fn read_block(&mut self, addr: Address, count: usize, store: &mut Store,
+            core_addr: Address) -> Result<(), BackingStoreError> {
+        /* Transfers count words from the CBS to the main store. The transfer
+        stops at the first parity failure, which is latched in parity_error */
+
+        self.clock.advance(START_TIME);
+        for x in 0..count as u64 {
+            self.clock.advance(WORD_TIME);
+            match self.read_word(addr + x) {
+                Ok(word) => store.write(core_addr + x, word)?,
+                Err(e) => {
+                    if let BackingStoreError::Parity(_) = e {
+                        self.parity_error = true;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write_block(&mut self, addr: Address, count: usize, store: &mut Store,
+            core_addr: Address) -> Result<(), BackingStoreError> {
+        /* Transfers count words from the main store to the CBS */
+
+        self.clock.advance(START_TIME);
+        for x in 0..count as u64 {
+            self.clock.advance(WORD_TIME);
+            let word = store.read(core_addr + x)?;
+            self.write_word(addr + x, word)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn inject_fault(&mut self, addr: Address, bit: u8) -> Result<(), BackingStoreError> {
+        /* Inverts one bit of a stored word (0-38 data, 39 parity) without
+        regenerating parity, so the next read of that word fails */
+
+        let x = self.index(addr)?;
+        self.words[x] ^= 1 << (bit.min(39));
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.parity_error = false;
+    }
+} // impl BackingStore
+
+impl Device for BackingStore {
+
+    fn name(&self) -> &str {
+        "CBS"
+    }
+
+    fn status(&self) -> u64 {
+        self.parity_error as u64
+    }
+
+    fn transfer(&mut self, output: bool, device_addr: u64, count: usize,
+            store: &mut Store, addr: u64) -> Result<bool, StopReason> {
+        let result = if output {
+            self.write_block(device_addr, count, store, addr)
+        } else {
+            self.read_block(device_addr, count, store, addr)
+        };
+
+        match result {
+            Ok(()) => Ok(true),
+            Err(BackingStoreError::Parity(a)) => Err(StopReason::Parity(a)),
+            Err(BackingStoreError::NonExistent(a)) => Err(StopReason::InvalidAddress(a)),
+            Err(BackingStoreError::Store(e)) => Err(e.into())
+        }
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], _media: &Path) -> Option<CommandResult> {
+        match code {
+            "BSFLT" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|(addr, bit): (u64, u8)| self.inject_fault(addr, bit).map_err(Into::into))),
+            _ => None
+        }
+    }
+
+    fn reset(&mut self) {
+        BackingStore::reset(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for BackingStore
+
+impl Snapshot for BackingStore {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(out, &(&self.words, self.parity_error))
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (words, parity_error): (Vec<u64>, bool) = deserialize_from(input)?;
+        if words.len() != self.words.len() {
+            return Err(snapshot::mismatch(format!("snapshot CBS has {} words, this machine {}",
+                    words.len(), self.words.len())));
+        }
+
+        self.words = words;
+        self.parity_error = parity_error;
+        Ok(())
+    }
+} // impl Snapshot for BackingStore
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{new_state, run_slice};
+    use super::super::pacing::Speed;
+    use super::super::processor::{instruction, word};
+
+    #[test]
+    fn parity_kept_per_word() {
+        let mut cbs = BackingStore::new(16, Arc::new(EmulationClock::new(0.0)));
+        cbs.write_word(3, 0o1234).unwrap();
+        assert_eq!(cbs.read_word(3), Ok(0o1234));
+        cbs.inject_fault(3, 39).unwrap();
+        assert_eq!(cbs.read_word(3), Err(BackingStoreError::Parity(3)));
+        cbs.write_word(3, 0o1234).unwrap();
+        assert_eq!(cbs.read_word(3), Ok(0o1234));
+        assert_eq!(cbs.read_word(16), Err(BackingStoreError::NonExistent(16)));
+    }
+
+    #[test]
+    fn parity_failure_stops_transfer() {
+        // Word 0 reads CBS words 100-102 into store words 200-202; the
+        // second has a fault injected
+        let mut state = new_state(Speed::Unthrottled);
+        state.power_on = true;
+        let cbs = state.bus.get_mut::<BackingStore>().unwrap();
+        for x in 0..3 {
+            cbs.write_word(100 + x, 0o11 * (x + 1)).unwrap();
+        }
+        cbs.inject_fault(101, 3).unwrap();
+        state.store.poke(0, word(instruction(0o75, 200), false, instruction(0o40, 1))).unwrap();
+        state.store.poke(1, word(instruction(0o40, 1), false, instruction(0o40, 1))).unwrap();
+        state.processor.a_reg.set(3 << 21 | 100);
+        state.processor.running = true;
+
+        run_slice(&mut state);
+        assert_eq!(state.last_stop, Some(StopReason::Parity(101)));
+        assert_eq!((state.error_glow, state.bs_parity_glow), (1.0, 1.0));
+        assert_eq!((state.store.peek(200), state.store.peek(201)), (Ok(0o11), Ok(0)));
+        let cbs = state.bus.get::<BackingStore>().unwrap();
+        assert!(cbs.parity_error);
+        assert_eq!(Device::status(cbs), 1);
+
+        // RESET clears the latched failure
+        state.bus.reset();
+        assert!(!state.bus.get::<BackingStore>().unwrap().parity_error);
+    }
+}
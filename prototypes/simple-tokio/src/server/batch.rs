@@ -0,0 +1,425 @@
+/***********************************************************************
+* simple-tokio/src/server/batch.rs
+*   Module "batch" runs the 503 without a panel: mounts tapes named on
+*   the command line, boots the program through Initial Instructions,
+*   runs until it halts or a time limit expires, and writes the
+*   peripheral output to host files.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* The program tapes are read by the Initial Instructions from the paper
+* tape reader in turn. When the processor waits on the reader at the end
+* of one tape, the next is mounted and INIT pressed again, so a program
+* is normally given as its binary tape followed by an entry tape. Tapes
+* given with --data follow the program tapes, for the program itself to
+* read: each is mounted when the processor waits on the reader at the
+* end of the one before, and the reader simply resumes, without INIT.
+*
+* A run ends when the processor stops, when the emulation clock passes
+* the time limit, or when the processor waits on something no one will
+* supply, such as the end of the last reader tape or a key at the
+* teleprinter. The process exit status tells which:
+*       0   dynamic stop (the program halted normally)
+*       1   error stop (invalid address, protection, parity, etc.)
+*       2   time limit expired
+*       3   stalled waiting for input
+*       4   host error (unreadable tape or snapshot, output file, etc.)
+*      64   usage error in the command line
+* Unless --speed is given, the processor runs unthrottled.
+*
+* With --restore, the run continues from a snapshot instead of booting.
+* With --load, tapes are read on the host, and the program is placed in
+* the store directly (see loader.rs) and entered at once. In either case
+* program tapes are optional and INIT is never pressed: the tapes are
+* mounted as data tapes are, only when the processor waits on the reader
+* at the end of its tape, so words 0-3 keep whatever the program put
+* there. With --snapshot, the state at the end of the run is saved. With
+* --trace, each instruction executed is recorded; list the file with
+* "simple-tokio trace".
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Start from and save machine snapshots.
+*   Write an instruction trace.
+*   Preload programs from tape images.
+*   Change tapes only when the processor waits on the reader.
+*   Preload absolute binary tapes only.
+*   Press INIT only while booting; add data tapes read without INIT.
+*   Give host and usage errors exit statuses of their own.
+***********************************************************************/
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use super::{ServerState, Result, new_state, initial_instructions, run_slice};
+use super::snapshot;
+use super::loader::{self, Loader};
+use super::trace::{self, Tracer};
+use super::magnetic_tape::MagneticTape;
+use super::pacing::Speed;
+use super::plotter::Plotter;
+use super::processor::StopReason;
+use super::register::EmulationTick;
+use super::line_printer::LinePrinter;
+use super::tape_punch::TapePunch;
+use super::tape_reader::TapeReader;
+use super::typewriter::Typewriter;
+
+pub const EXIT_HALT: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_TIME_LIMIT: i32 = 2;
+pub const EXIT_STALLED: i32 = 3;
+pub const EXIT_HOST_ERROR: i32 = 4;
+pub const EXIT_USAGE: i32 = 64;
+
+const DEFAULT_LIMIT: EmulationTick = 600.0;     // sec of emulated time
+
+const USAGE: &str = "\
+Usage: simple-tokio run [options] [program-tape...]
+  --punch FILE          write the punched tape to FILE
+  --printer FILE        write the line printer output to FILE (listing in FILE.lst)
+  --plot FILE           save the plotter drawing to FILE (.svg or .png)
+  --typewriter FILE     write the teleprinter output to FILE instead of stdout
+  --keyin TEXT          type TEXT at the teleprinter keyboard
+  --data FILE           mount FILE in the reader after the program tapes,
+                        for the program to read
+  --mtape N=FILE        mount FILE on magnetic tape handler N with a write ring
+  --mtape-ro N=FILE     mount FILE on magnetic tape handler N without a write ring
+  --limit SECONDS       stop after SECONDS of emulated time (default 600)
+  --speed RATIO         run at RATIO times real time (default unthrottled)
+  --restore FILE        continue from the snapshot in FILE instead of booting
+  --load FILE           place the program on absolute binary tape FILE in the
+                        store without booting
+  --snapshot FILE       save the machine state in FILE at the end of the run
+  --trace FILE          record each instruction executed in FILE
+  --trace-range A-B     trace only instructions in words A through B";
+
+#[derive(Debug, Default)]
+struct Options {
+    programs: Vec<PathBuf>,           // tapes for the Initial Instructions, in order
+    data: Vec<PathBuf>,               // tapes for the program, in order
+    punch: Option<PathBuf>,
+    printer: Option<PathBuf>,
+    plot: Option<PathBuf>,
+    typewriter: Option<PathBuf>,
+    keyin: String,
+    mtapes: Vec<(usize, PathBuf, bool)>,  // handler, image, write ring
+    limit: EmulationTick,
+    speed: Option<f64>,
+    restore: Option<PathBuf>,
+    loads: Vec<PathBuf>,              // tapes to preload, in order
+    snapshot: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    trace_range: Option<String>
+}
+
+fn parse_mtape(value: &str, write_enabled: bool) -> Result<(usize, PathBuf, bool)> {
+    match value.split_once('=') {
+        Some((unit, path)) => Ok((unit.parse()?, PathBuf::from(path), write_enabled)),
+        None => Err(format!("magnetic tape must be given as N=FILE: {}", value).into())
+    }
+}
+
+fn parse_options(args: &[&str]) -> Result<Options> {
+    let mut options = Options {limit: DEFAULT_LIMIT, ..Options::default()};
+    let mut args = args.iter();
+
+    while let Some(&arg) = args.next() {
+        if !arg.starts_with("--") {
+            options.programs.push(PathBuf::from(arg));
+            continue;
+        }
+
+        let value = match args.next() {
+            Some(&value) => value,
+            None => return Err(USAGE.into())
+        };
+
+        match arg {
+            "--punch" => options.punch = Some(value.into()),
+            "--printer" => options.printer = Some(value.into()),
+            "--plot" => options.plot = Some(value.into()),
+            "--typewriter" => options.typewriter = Some(value.into()),
+            "--data" => options.data.push(value.into()),
+            "--keyin" => {
+                options.keyin.push_str(value);
+                options.keyin.push('\n');
+            }
+            "--mtape" => options.mtapes.push(parse_mtape(value, true)?),
+            "--mtape-ro" => options.mtapes.push(parse_mtape(value, false)?),
+            "--limit" => options.limit = value.parse()?,
+            "--speed" => options.speed = Some(value.parse()?),
+            "--restore" => options.restore = Some(value.into()),
+            "--load" => options.loads.push(value.into()),
+            "--snapshot" => options.snapshot = Some(value.into()),
+            "--trace" => options.trace = Some(value.into()),
+            "--trace-range" => options.trace_range = Some(value.to_string()),
+            _ => return Err(USAGE.into())
+        }
+    }
+
+    if options.programs.is_empty() && options.restore.is_none() && options.loads.is_empty() {
+        Err(USAGE.into())
+    } else {
+        Ok(options)
+    }
+}
+
+fn mount(state: &mut ServerState, options: &Options) -> Result<()> {
+    /* Loads the tapes and paper named in the options */
+
+    let bus = &mut state.bus;
+    if let Some(path) = options.punch.as_ref() {
+        if let Some(punch) = bus.get_mut::<TapePunch>() {
+            punch.mount(path)?;
+        }
+    }
+
+    if let Some(path) = options.printer.as_ref() {
+        if let Some(printer) = bus.get_mut::<LinePrinter>() {
+            printer.mount(path)?;
+        }
+    }
+
+    if let Some(mtape) = bus.get_mut::<MagneticTape>() {
+        for (unit, path, write_enabled) in options.mtapes.iter() {
+            mtape.mount(*unit, path, *write_enabled)?;
+        }
+    }
+
+    if let Some(typewriter) = bus.get_mut::<Typewriter>() {
+        typewriter.type_text(&options.keyin);
+    }
+
+    Ok(())
+}
+
+fn unload(state: &mut ServerState, options: &Options) -> Result<()> {
+    /* Writes the peripheral output to the host files */
+
+    let bus = &mut state.bus;
+    bus.power(false);
+    if let Some(punch) = bus.get_mut::<TapePunch>() {
+        punch.unload()?;
+    }
+
+    if let Some(printer) = bus.get_mut::<LinePrinter>() {
+        printer.unload()?;
+    }
+
+    if let Some(mtape) = bus.get_mut::<MagneticTape>() {
+        mtape.flush()?;
+    }
+
+    if let Some(path) = options.plot.as_ref() {
+        if let Some(plotter) = bus.get::<Plotter>() {
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("png") => plotter.save_png(path)?,
+                _ => plotter.save_svg(path)?
+            }
+        }
+    }
+
+    if let Some(typewriter) = bus.get::<Typewriter>() {
+        match options.typewriter.as_ref() {
+            Some(path) => fs::write(path, typewriter.paper())?,
+            None => print!("{}", typewriter.paper())
+        }
+    }
+
+    Ok(())
+}
+
+fn power_on(state: &mut ServerState) {
+    /* Powers the machine on, as the operator would before pressing INIT */
+
+    state.power_on = true;
+    state.store.set_protection(true);
+    state.bus.power(true);
+    state.pacer.anchor();
+}
+
+fn next_tape(state: &mut ServerState, tapes: &mut dyn Iterator<Item = (&PathBuf, bool)>) -> Result<bool> {
+    /* Mounts the next tape in the reader, pressing INIT if the tape is to
+    be read by the Initial Instructions. Returns false if there are no
+    more tapes */
+
+    match tapes.next() {
+        Some((path, init)) => {
+            if let Some(reader) = state.bus.get_mut::<TapeReader>() {
+                reader.mount(path)?;
+            }
+
+            if init {
+                initial_instructions(state);
+            }
+            Ok(true)
+        }
+        None => Ok(false)
+    }
+}
+
+fn execute(state: &mut ServerState, programs: &[PathBuf], data: &[PathBuf],
+        boot: bool, limit: EmulationTick) -> Result<i32> {
+    /* Runs the processor until it stops, stalls, or has run for limit
+    seconds of emulated time, returning the exit status. If boot is set,
+    the program tapes are read by pressing INIT; otherwise they, like the
+    data tapes, are mounted for the waiting reader to resume */
+
+    let end = state.eclock.read() + limit;
+    let mut tapes = programs.iter().map(|path| (path, boot))
+            .chain(data.iter().map(|path| (path, false)));
+    if boot {
+        next_tape(state, &mut tapes)?;
+    }
+
+    loop {
+        let idle = run_slice(state);
+        match state.last_stop {
+            Some(StopReason::DynamicStop(_)) => return Ok(EXIT_HALT),
+            Some(_) => return Ok(EXIT_ERROR),
+            None => {}
+        }
+
+        if state.eclock.read() >= end {
+            println!("time limit of {} sec expired", limit);
+            return Ok(EXIT_TIME_LIMIT);
+        }
+
+        if idle {
+            // The loader or program waits on the reader at the end of each tape
+            let loaded = state.bus.waiting().and_then(|d| d.as_any().downcast_ref::<TapeReader>())
+                    .is_some_and(|r| r.remaining() == 0);
+            if !(loaded && next_tape(state, &mut tapes)?) {
+                println!("processor stalled at {} waiting for input", state.processor.scr.read());
+                return Ok(EXIT_STALLED);
+            }
+        }
+
+        let delay = state.pacer.delay();
+        if delay > Duration::from_secs(0) {
+            thread::sleep(delay);
+        }
+    }
+}
+
+pub fn main(args: &[&str]) -> i32 {
+    /* Runs a program tape headless, returning the process exit status.
+    Errors on the host side are reported here, with statuses of their own
+    so that a script can tell them from the program's error stops */
+
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    match run(&options) {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_HOST_ERROR
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<i32> {
+    let speed = match options.speed {
+        Some(ratio) if ratio > 0.0 => Speed::Ratio(ratio),
+        _ => Speed::Unthrottled
+    };
+
+    let mut state = new_state(speed);
+    match options.restore.as_ref() {
+        Some(path) => snapshot::restore(&mut state, path)?,
+        None => power_on(&mut state)
+    }
+
+    mount(&mut state, options)?;
+    if let Some(path) = options.trace.as_ref() {
+        let range = match options.trace_range.as_ref() {
+            Some(text) => Some(trace::parse_range(text)?),
+            None => None
+        };
+        state.tracer = Some(Tracer::create(path, range)?);
+    }
+
+    if !options.loads.is_empty() {
+        let mut loader = Loader::new();
+        for path in options.loads.iter() {
+            loader::load_file(&mut loader, path, &mut state.store)?;
+        }
+
+        loader::enter(&mut state, &loader)?;
+        println!("{} words loaded", loader.words);
+    }
+
+    let start = state.eclock.read();
+    let boot = options.restore.is_none() && options.loads.is_empty();
+    let status = execute(&mut state, &options.programs, &options.data, boot, options.limit)?;
+    if let Some(path) = options.snapshot.as_ref() {
+        snapshot::save(&state, path)?;
+    }
+
+    unload(&mut state, options)?;
+    if let Some(mut tracer) = state.tracer.take() {
+        tracer.flush()?;
+        println!("{} instructions traced", tracer.records());
+    }
+
+    println!("run ended at {:.6} sec emulated time, status {}", state.eclock.read() - start, status);
+    Ok(status)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::processor::{instruction, word};
+
+    fn tape_file(name: &str, tape: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("batch-{}-{}", std::process::id(), name));
+        fs::write(&path, tape).unwrap();
+        path
+    }
+
+    #[test]
+    fn data_tapes_resume_without_init() {
+        // A preloaded program reads one character from each of two data
+        // tapes and halts. Words 0-3 must not be replaced by the Initial
+        // Instructions when the reader reaches the end of a tape
+        let mut state = new_state(Speed::Unthrottled);
+        power_on(&mut state);
+        let program = [
+            word(instruction(0o71, 0), false, instruction(0o40, 101)),      // 100
+            word(instruction(0o71, 0), false, instruction(0o40, 102)),      // 101
+            word(instruction(0o40, 102), false, instruction(0o40, 102))     // 102
+        ];
+        for addr in 0..4 {
+            state.store.poke(addr, 0o1111111111111 * (addr + 1)).unwrap();
+        }
+        for (x, &w) in program.iter().enumerate() {
+            state.store.poke(100 + x as u64, w).unwrap();
+        }
+        state.processor.jump(100, false);
+        state.processor.running = true;
+
+        let data = [tape_file("data1", &[0o01]), tape_file("data2", &[0o02])];
+        let status = execute(&mut state, &[], &data, false, 10.0).unwrap();
+        for path in data.iter() {
+            fs::remove_file(path).unwrap();
+        }
+
+        assert_eq!(status, EXIT_HALT);
+        assert_eq!(state.processor.a_reg.read(), 0o03);
+        for addr in 0..4 {
+            assert_eq!(state.store.peek(addr), Ok(0o1111111111111 * (addr + 1)));
+        }
+    }
+}
@@ -0,0 +1,391 @@
+/***********************************************************************
+* simple-tokio/src/server/device.rs
+*   Module "device" defines the trait implemented by each 503 peripheral
+*   and the I/O bus that routes processor I/O to the peripherals by
+*   their unit numbers.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Character units (functions 71, 72, 74 and 77) and block-transfer
+* units (functions 75 and 76) are separate address spaces, so a device
+* may be attached at a unit in either or both. A character unit with no
+* device attached never becomes ready for input, so the processor waits
+* on it as it would on an empty reader; output and control orders to one
+* are discarded. A block transfer to a unit with no device attached
+* stops the processor with StopReason::NoUnit.
+*
+* When a device is not ready, the processor waits and retries the
+* instruction. The bus remembers which device refused, so the server
+* can ask when that device will next be ready and skip emulated time
+* forward to that point instead of spinning through the wait.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Track the device the processor is waiting on and its ready time.
+*   Save and restore the devices in snapshots.
+*   Document what CLEAR and RESET do to a device; CLEAR ends any wait.
+*   Tell which device the processor is waiting on.
+*   Let the bus flush the devices' host files.
+*   Stop on a block transfer to a unit with no device attached.
+*   Pass the media directory to device commands.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use bincode::{serialize_into, deserialize_from};
+
+use super::processor::{self, IoSystem, StopReason};
+use super::register::EmulationTick;
+use super::snapshot::{self, Snapshot, SnapshotResult};
+use super::store::Store;
+
+pub type CommandResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+pub trait Device: Any + Send + Snapshot {
+    fn name(&self) -> &str;
+
+    fn read_char(&mut self) -> Option<u8> {
+        None
+    }
+
+    fn write_char(&mut self, _ch: u8) -> bool {
+        true
+    }
+
+    fn control(&mut self, _order: u64) -> bool {
+        true
+    }
+
+    fn status(&self) -> u64 {
+        0
+    }
+
+    fn transfer(&mut self, _output: bool, _device_addr: u64, _count: usize,
+            _store: &mut Store, _addr: u64) -> Result<bool, StopReason> {
+        Ok(false)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        /* Returns the emulated time at which a device that has just refused
+        an operation will be ready, or None if it is waiting for something
+        outside the machine, such as the operator */
+        None
+    }
+
+    fn command(&mut self, _code: &str, _payload: &[u8], _media: &Path) -> Option<CommandResult> {
+        /* Handles a device-specific message from a panel, returning None if
+        the code is not one of this device's. File names in the message are
+        resolved within the media directory (see media.rs) */
+        None
+    }
+
+    fn clear(&mut self) {
+        /* Stops the device for the CLEAR button: any operation the processor
+        is waiting on is abandoned, and busy and hold lamps go out */
+    }
+
+    fn reset(&mut self) {
+        /* Clears the device's error conditions for the RESET button */
+    }
+
+    fn power(&mut self, _on: bool) {}
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        /* Writes any output still buffered to the device's host files */
+        Ok(())
+    }
+
+    fn update_lamps(&mut self) {}
+
+    fn glow(&self) -> f32 {
+        /* Activity lamp intensity reported to the panels */
+        0.0
+    }
+
+    fn transfer_glow(&self) -> f32 {
+        /* Contribution to the TRANSFER lamp */
+        0.0
+    }
+
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+#[derive(Default)]
+pub struct IoBus {
+    devices: Vec<Box<dyn Device>>,
+    units: BTreeMap<u64, usize>,        // character unit -> device index
+    block_units: BTreeMap<u64, usize>,  // block-transfer unit -> device index
+    waiting: Option<usize>              // device that refused the last operation
+}
+
+impl IoBus {
+
+    pub fn new() -> Self {
+        IoBus::default()
+    }
+
+    pub fn attach(&mut self, units: &[u64], block_units: &[u64], device: Box<dyn Device>) {
+        /* Registers a device at its character and block-transfer units */
+
+        let x = self.devices.len();
+        self.devices.push(device);
+        for &unit in units {
+            self.units.insert(unit, x);
+        }
+
+        for &unit in block_units {
+            self.block_units.insert(unit, x);
+        }
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &dyn Device> {
+        self.devices.iter().map(|d| d.as_ref())
+    }
+
+    pub fn get<T: Device>(&self) -> Option<&T> {
+        self.devices.iter().find_map(|d| d.as_any().downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: Device>(&mut self) -> Option<&mut T> {
+        self.devices.iter_mut().find_map(|d| d.as_any_mut().downcast_mut::<T>())
+    }
+
+    fn unit(&self, unit: u64) -> Option<usize> {
+        self.units.get(&unit).copied()
+    }
+
+    fn record_wait(&mut self, x: usize, ready: bool) {
+        self.waiting = if ready {None} else {Some(x)};
+    }
+
+    pub fn waiting(&self) -> Option<&dyn Device> {
+        /* Returns the device that refused the last operation, if any */
+        self.waiting.map(|x| self.devices[x].as_ref())
+    }
+
+    pub fn ready_time(&self) -> Option<EmulationTick> {
+        /* Returns the time at which the device the processor is waiting on
+        will be ready, or None if that time is not known */
+
+        self.waiting.and_then(|x| self.devices[x].ready_time())
+    }
+
+    pub fn command(&mut self, code: &str, payload: &[u8], media: &Path) -> Option<CommandResult> {
+        self.devices.iter_mut().find_map(|d| d.command(code, payload, media))
+    }
+
+    pub fn clear(&mut self) {
+        self.waiting = None;
+        for d in self.devices.iter_mut() {
+            d.clear();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for d in self.devices.iter_mut() {
+            d.reset();
+        }
+    }
+
+    pub fn power(&mut self, on: bool) {
+        for d in self.devices.iter_mut() {
+            d.power(on);
+        }
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        for d in self.devices.iter_mut() {
+            d.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update_lamps(&mut self) {
+        for d in self.devices.iter_mut() {
+            d.update_lamps();
+        }
+    }
+
+    pub fn transfer_glow(&self) -> f32 {
+        self.devices.iter().map(|d| d.transfer_glow()).fold(0.0, f32::max)
+    }
+} // impl IoBus
+
+impl Snapshot for IoBus {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        /* Saves each device preceded by its name, in the order attached */
+
+        serialize_into(&mut *out, &self.devices.len())?;
+        for d in self.devices.iter() {
+            serialize_into(&mut *out, d.name())?;
+            d.save(out)?;
+        }
+
+        Ok(())
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let count: usize = deserialize_from(&mut *input)?;
+        if count != self.devices.len() {
+            return Err(snapshot::mismatch(format!("snapshot has {} devices, this machine {}",
+                    count, self.devices.len())));
+        }
+
+        for d in self.devices.iter_mut() {
+            let name: String = deserialize_from(&mut *input)?;
+            if name != d.name() {
+                return Err(snapshot::mismatch(format!("snapshot device {} does not match {}",
+                        name, d.name())));
+            }
+            d.restore(input)?;
+        }
+
+        self.waiting = None;
+        Ok(())
+    }
+} // impl Snapshot for IoBus
+
+impl IoSystem for IoBus {
+
+    fn read_char(&mut self, unit: u64) -> Option<u8> {
+        match self.unit(unit) {
+            Some(x) => {
+                let ch = self.devices[x].read_char();
+                self.record_wait(x, ch.is_some());
+                ch
+            }
+            None => {
+                self.waiting = None;
+                None
+            }
+        }
+    }
+
+    fn write_char(&mut self, unit: u64, ch: u8) -> bool {
+        match self.unit(unit) {
+            Some(x) => {
+                let ready = self.devices[x].write_char(ch);
+                self.record_wait(x, ready);
+                ready
+            }
+            None => true
+        }
+    }
+
+    fn control(&mut self, unit: u64, order: u64) -> bool {
+        match self.unit(unit) {
+            Some(x) => {
+                let ready = self.devices[x].control(order);
+                self.record_wait(x, ready);
+                ready
+            }
+            None => true
+        }
+    }
+
+    fn status(&mut self, unit: u64) -> u64 {
+        match self.unit(unit) {
+            Some(x) => self.devices[x].status(),
+            None => 0
+        }
+    }
+
+    fn transfer(&mut self, output: bool, param: u64, addr: u64, store: &mut Store) -> Result<bool, StopReason> {
+        match self.block_units.get(&processor::transfer_unit(param)).copied() {
+            Some(x) => {
+                let result = self.devices[x].transfer(output, processor::transfer_address(param),
+                        processor::transfer_count(param), store, addr);
+                self.record_wait(x, !matches!(result, Ok(false)));
+                result
+            }
+            None => {
+                self.waiting = None;
+                Err(StopReason::NoUnit(processor::transfer_unit(param)))
+            }
+        }
+    }
+} // impl IoSystem for IoBus
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
+    use bincode::serialize;
+    use super::super::register::EmulationClock;
+    use super::super::tape_punch::{self, TapePunch};
+    use super::super::tape_reader::TapeReader;
+
+    fn bus(clock: &Arc<EmulationClock>) -> IoBus {
+        let mut bus = IoBus::new();
+        bus.attach(&[0], &[], Box::new(TapeReader::new(clock.clone())));
+        bus.attach(&[1], &[], Box::new(TapePunch::new(clock.clone())));
+        bus
+    }
+
+    #[test]
+    fn waits_recorded() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut bus = bus(&clock);
+
+        // An empty reader waits on the operator, with no ready time
+        assert_eq!(bus.read_char(0), None);
+        assert_eq!(bus.waiting().map(|d| d.name()), Some("READER"));
+        assert_eq!(bus.ready_time(), None);
+
+        // A busy punch will be ready one character period on
+        assert!(bus.write_char(1, 0o21));
+        assert!(bus.waiting().is_none());
+        assert!(!bus.write_char(1, 0o22));
+        assert_eq!(bus.waiting().map(|d| d.name()), Some("PUNCH"));
+        assert_eq!(bus.ready_time(), Some(tape_punch::CHAR_PERIOD));
+
+        // No device at the unit: input never ready, output discarded
+        assert_eq!(bus.read_char(7), None);
+        assert!(bus.waiting().is_none());
+        assert!(bus.write_char(7, 0));
+
+        bus.clear();
+        assert!(bus.waiting().is_none());
+    }
+
+    #[test]
+    fn unattached_block_unit_stops() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut bus = bus(&clock);
+        let mut store = Store::new(1, clock);
+        let param = 6 << 34 | 1 << 21 | 100;
+        assert_eq!(bus.transfer(false, param, 200, &mut store), Err(StopReason::NoUnit(6)));
+        assert!(bus.waiting().is_none());
+    }
+
+    #[test]
+    fn commands_named_within_media() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut bus = bus(&clock);
+        let media = std::env::temp_dir().join(format!("media-{}", std::process::id()));
+        fs::create_dir_all(&media).unwrap();
+        fs::write(media.join("tape.rt"), [1, 2, 3]).unwrap();
+
+        let mount = |name: &str| serialize(name).unwrap();
+        assert!(matches!(bus.command("TRMNT", &mount("tape.rt"), &media), Some(Ok(()))));
+        assert_eq!(bus.get::<TapeReader>().unwrap().remaining(), 3);
+        for name in &["../tape.rt", "/etc/passwd", ""] {
+            assert!(matches!(bus.command("TRMNT", &mount(name), &media), Some(Err(_))));
+        }
+        assert!(bus.command("NOSUCH", &[], &media).is_none());
+        fs::remove_dir_all(&media).unwrap();
+    }
+}
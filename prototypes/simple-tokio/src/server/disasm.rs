@@ -0,0 +1,236 @@
+/***********************************************************************
+* simple-tokio/src/server/disasm.rs
+*   Module "disasm" decodes 503 words into their two instructions and
+*   their numeric values, for the monitor, the trace listing, and the
+*   "simple-tokio disasm" store dump.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* An instruction is shown as its function in two octal digits (group
+* and code) and its address in decimal, e.g. "40 102". The two
+* instructions of a word are separated by ":", or by "/" when the B
+* digit is set and the second instruction is modified by the contents
+* of the first one's address. A word is also shown as a signed integer
+* and as a signed fraction, its usual interpretation, where the sign
+* digit has weight -1 and the others 2^-1 through 2^-38.
+*
+* "simple-tokio disasm" decodes octal words given on the command line,
+* or lists the store saved in a snapshot file:
+*       simple-tokio disasm word...
+*       simple-tokio disasm --snapshot FILE [first-last]
+* Words from the command line are followed by what their instructions
+* do. Without a range, zero words in the snapshot are not listed.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Write the double-length accumulator as "A,AR", distinct from AR.
+*   Read only the store from a snapshot, leaving device files alone.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::io::{self, Write};
+
+use super::{Result, new_state};
+use super::pacing::Speed;
+use super::processor::{self, AUX_BITS};
+use super::snapshot;
+use super::store::{Address, Word, WORD_MASK};
+use super::trace::parse_range;
+
+const USAGE: &str = "\
+Usage: simple-tokio disasm word...
+       simple-tokio disasm --snapshot FILE [first-last]";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instruction {
+    pub function: u64,
+    pub address: u64
+}
+
+impl Instruction {
+
+    pub fn new(instr: u64) -> Self {
+        Instruction {
+            function: processor::function(instr),
+            address: processor::address(instr)
+        }
+    }
+
+    pub fn group(&self) -> u64 {
+        self.function >> 3
+    }
+
+    pub fn code(&self) -> u64 {
+        self.function & 7
+    }
+
+    pub fn describe(&self) -> String {
+        /* Returns what the instruction does, in the notation of the 503
+        manuals: A' is the new content of A, n the store word addressed.
+        A,AR is the double-length accumulator; AR alone is the auxiliary
+        register */
+
+        let code = self.code();
+        let operand = |x: &str| match code {
+            0 => x.to_string(),
+            1 => format!("-{}", x),
+            2 => "n + 1".to_string(),
+            3 => "A & n".to_string(),
+            4 => "A + n".to_string(),
+            5 => "A - n".to_string(),
+            6 => "0".to_string(),
+            _ => "n - A".to_string()
+        };
+
+        let target = if code < 4 {"first"} else {"second"};
+        match self.group() {
+            0 => format!("A' = {}", operand("A")),
+            1 => format!("A' = {}, n' = A", operand("n")),
+            2 => format!("n' = {}", operand("A")),
+            3 => format!("A' = n, n' = {}", operand("n")),
+            4 => match code & 3 {
+                0 => format!("jump to {} instruction", target),
+                1 => format!("jump to {} instruction if A negative", target),
+                2 => format!("jump to {} instruction if A zero", target),
+                _ => format!("jump to {} instruction if overflow, clear overflow", target)
+            },
+            5 => match code {
+                0 => "A,AR' = A,AR * 2^-N".to_string(),
+                1 => "A' = A * 2^-N, logical; AR' = 0".to_string(),
+                2 => "A,AR' = A * n, double length".to_string(),
+                3 => "A' = A * n, rounded; AR' = 0".to_string(),
+                4 => "A,AR' = A,AR * 2^N".to_string(),
+                5 => "A' = A * 2^N; AR' = 0".to_string(),
+                6 => "A' = A,AR / n; AR' = 0".to_string(),
+                _ => "A' = AR".to_string()
+            },
+            6 => match code {
+                0 => "A' = A + n, floating".to_string(),
+                1 => "A' = A - n, floating".to_string(),
+                2 => "A' = n - A, floating".to_string(),
+                3 => "A' = A * n, floating".to_string(),
+                4 => "A' = A / n, floating".to_string(),
+                5 => "A' = A standardized".to_string(),
+                _ => "unassigned".to_string()
+            },
+            _ => match code {
+                0 => "A' = word generator".to_string(),
+                1 => "A' = A | input character".to_string(),
+                2 => "control order to unit".to_string(),
+                3 => "n' = SCR".to_string(),
+                4 => "output character to unit".to_string(),
+                5 => "block transfer in".to_string(),
+                6 => "block transfer out".to_string(),
+                _ => "A' = unit status".to_string()
+            }
+        }
+    }
+} // impl Instruction
+
+pub fn decode(word: Word) -> (Instruction, bool, Instruction) {
+    /* Splits a word into its first instruction, B digit, and second
+    instruction */
+
+    (Instruction::new(processor::first_half(word)), processor::is_b_modified(word),
+            Instruction::new(processor::second_half(word)))
+}
+
+pub fn fraction(word: Word) -> f64 {
+    /* Returns the value of a word as a fraction, -1 <= f < 1 */
+    processor::to_signed(word & WORD_MASK) as f64 / (1_u64 << AUX_BITS) as f64
+}
+
+pub fn format_instruction(instr: u64) -> String {
+    let instr = Instruction::new(instr);
+    format!("{:02o} {:<4}", instr.function, instr.address)
+}
+
+pub fn format_instructions(word: Word) -> String {
+    /* Shows a word as its two instructions */
+
+    let (first, b_modify, second) = decode(word);
+    format!("{:02o} {:<4} {} {:02o} {:<4}", first.function, first.address,
+            if b_modify {'/'} else {':'}, second.function, second.address)
+}
+
+pub fn format_value(word: Word) -> String {
+    /* Shows a word as a signed integer and a signed fraction */
+    format!("{:+14} {:+.12}", processor::to_signed(word & WORD_MASK), fraction(word))
+}
+
+pub fn format_word(word: Word) -> String {
+    /* Shows a word in octal, as its two instructions, and as numbers */
+    format!("{:013o}  {}  {}", word, format_instructions(word), format_value(word))
+}
+
+pub fn explain(word: Word) -> String {
+    /* Describes the two instructions of a word on separate lines */
+
+    let (first, b_modify, second) = decode(word);
+    format!("       group {} code {}  {}\n       group {} code {}  {}{}\n",
+            first.group(), first.code(), first.describe(),
+            second.group(), second.code(), second.describe(),
+            if b_modify {", address modified by n of the first"} else {""})
+}
+
+pub fn dump(store: &[(Address, Word)], explained: bool, out: &mut dyn Write) -> io::Result<()> {
+    /* Lists the words, one to a line, preceded by their addresses, and
+    optionally followed by what their instructions do */
+
+    for (addr, word) in store {
+        writeln!(out, "{:5}  {}", addr, format_word(*word))?;
+        if explained {
+            write!(out, "{}", explain(*word))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn main(args: &[&str]) -> Result<()> {
+    /* Decodes words or a snapshot's store on stdout */
+
+    let explained = !matches!(args.first(), Some(&"--snapshot"));
+    let words = match args {
+        [] => return Err(USAGE.into()),
+        ["--snapshot", path, rest @ ..] => {
+            let range = match rest {
+                [] => None,
+                [range] => Some(parse_range(range)?),
+                _ => return Err(USAGE.into())
+            };
+
+            let mut state = new_state(Speed::Unthrottled);
+            snapshot::restore_store(&mut state, path)?;
+            let size = state.store.size() as Address;
+            let addrs = range.clone().unwrap_or(0..=size.saturating_sub(1));
+            let mut words = Vec::new();
+            for addr in addrs.filter(|&a| a < size) {
+                let word = state.store.peek(addr)?;
+                if word != 0 || range.is_some() {
+                    words.push((addr, word));
+                }
+            }
+
+            words
+        }
+        _ => {
+            let mut words = Vec::new();
+            for (index, text) in args.iter().enumerate() {
+                match Word::from_str_radix(text, 8) {
+                    Ok(word) if word <= WORD_MASK => words.push((index as Address, word)),
+                    _ => return Err(format!("bad octal word {}", text).into())
+                }
+            }
+
+            words
+        }
+    };
+
+    let stdout = io::stdout();
+    dump(&words, explained, &mut stdout.lock())?;
+    Ok(())
+}
@@ -0,0 +1,224 @@
+/***********************************************************************
+* simple-tokio/src/server/floating.rs
+*   Module "floating" performs the 503's floating-point arithmetic
+*   exactly, in integers, for the group-6 functions.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* A floating-point word holds a mantissa in bits 38-9, a 30-bit two's-
+* complement fraction with a sign digit and 29 digits, and an exponent
+* in bits 8-0, biased by 256:
+*       value = mantissa * 2^(exponent - 256)
+* A number is standard when the sign digit and the first digit of the
+* mantissa differ, so that 1/2 <= mantissa < 1 or -1 <= mantissa < -1/2.
+* Zero is the all-zero word. Operands need not be standard; results
+* always are.
+*
+* Each result is formed exactly, then standardized and rounded to 29
+* digits by adding half a unit in the last place and truncating toward
+* minus infinity. Quotients are developed to 64 more digits than the
+* mantissa before rounding, which gives the same result as rounding
+* the exact quotient. A result whose exponent would exceed 511 is a
+* floating overflow: the operation leaves A unchanged and sets the
+* overflow flip-flop. A result whose exponent would fall below 0 is
+* replaced by zero.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use super::processor::{to_signed, AUX_BITS};
+use super::store::{Word, WORD_MASK};
+
+pub const MANTISSA_BITS: u32 = 30;      // including the sign digit
+pub const EXPONENT_BITS: u32 = 9;
+pub const EXPONENT_BIAS: i32 = 256;
+pub const EXPONENT_MASK: Word = 0o777;
+pub const MAX_EXPONENT: i32 = 511;
+
+const DIGITS: u32 = MANTISSA_BITS - 1;  // mantissa digits after the sign
+const QUOTIENT_GUARD: u32 = 64;         // extra quotient digits developed
+const ALIGN_LIMIT: i32 = 90;            // addend digits kept below the larger operand
+
+pub type FloatResult = Option<Word>;    // None on floating overflow
+
+pub fn unpack(word: Word) -> (i128, i32) {
+    /* Splits a floating-point word into an integer mantissa m and scale s
+    such that its value is m * 2^s */
+
+    let mantissa = to_signed(word) >> EXPONENT_BITS;
+    let exponent = (word & EXPONENT_MASK) as i32;
+    (mantissa as i128, exponent - EXPONENT_BIAS - DIGITS as i32)
+}
+
+fn bit_length(n: i128) -> u32 {
+    /* Returns the number of digits needed to hold n, excluding its sign */
+    128 - if n < 0 {!n} else {n}.leading_zeros()
+}
+
+pub fn pack(n: i128, scale: i32) -> FloatResult {
+    /* Standardizes and rounds the value n * 2^scale to a floating-point
+    word. Returns None on floating overflow */
+
+    if n == 0 {
+        return Some(0);
+    }
+
+    let mut shift = bit_length(n) as i32 - DIGITS as i32;
+    let mut m = if shift > 0 {
+        (n + (1 << (shift - 1))) >> shift
+    } else {
+        n << -shift
+    };
+
+    // Rounding may carry into the sign digit, or leave -1/2, which is
+    // not standard
+    if m == 1 << DIGITS {
+        m >>= 1;
+        shift += 1;
+    } else if m == -(1 << (DIGITS - 1)) {
+        m <<= 1;
+        shift -= 1;
+    }
+
+    let exponent = shift + scale + DIGITS as i32 + EXPONENT_BIAS;
+    if exponent > MAX_EXPONENT {
+        None
+    } else if exponent < 0 {
+        Some(0)
+    } else {
+        Some((((m as u64) << EXPONENT_BITS) | exponent as u64) & WORD_MASK)
+    }
+}
+
+fn sum(a: (i128, i32), b: (i128, i32)) -> (i128, i32) {
+    /* Adds two exact values. An addend too small to reach the digits kept
+    is reduced to its sign at the lowest place, which still settles the
+    rounding of a result exactly half-way between two numbers */
+
+    match (a, b) {
+        ((0, _), b) => b,
+        (a, (0, _)) => a,
+        _ => {
+            let (big, small) = if a.1 >= b.1 {(a, b)} else {(b, a)};
+            let diff = big.1 - small.1;
+            if diff > ALIGN_LIMIT {
+                ((big.0 << (ALIGN_LIMIT + 1)) + small.0.signum(), big.1 - ALIGN_LIMIT - 1)
+            } else {
+                ((big.0 << diff) + small.0, small.1)
+            }
+        }
+    }
+}
+
+pub fn add(a: Word, n: Word) -> FloatResult {
+    let (x, y) = (unpack(a), unpack(n));
+    let (m, s) = sum(x, y);
+    pack(m, s)
+}
+
+pub fn subtract(a: Word, n: Word) -> FloatResult {
+    /* Returns a - n */
+
+    let (x, (m, s)) = (unpack(a), unpack(n));
+    let (m, s) = sum(x, (-m, s));
+    pack(m, s)
+}
+
+pub fn multiply(a: Word, n: Word) -> FloatResult {
+    let ((x, xs), (y, ys)) = (unpack(a), unpack(n));
+    pack(x * y, xs + ys)
+}
+
+pub fn divide(a: Word, n: Word) -> FloatResult {
+    /* Returns a / n; division by zero is a floating overflow */
+
+    let ((x, xs), (y, ys)) = (unpack(a), unpack(n));
+    if y == 0 {
+        return None;
+    }
+
+    let dividend = x << QUOTIENT_GUARD;
+    let quotient = if y > 0 {dividend.div_euclid(y)} else {(-dividend).div_euclid(-y)};
+    pack(quotient, xs - ys - QUOTIENT_GUARD as i32)
+}
+
+pub fn standardize(a: Word) -> FloatResult {
+    /* Converts a fixed-point fraction to floating point */
+    pack(to_signed(a) as i128, -(AUX_BITS as i32))
+}
+
+pub fn value(word: Word) -> f64 {
+    /* Returns the value of a floating-point word, for display */
+
+    let (m, s) = unpack(word);
+    m as f64 * 2_f64.powi(s)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(mantissa: i64, exponent: i32) -> Word {
+        ((mantissa as u64) << EXPONENT_BITS | exponent as u64) & WORD_MASK
+    }
+
+    const HALF: i64 = 1 << (DIGITS - 1);
+
+    #[test]
+    fn standard_results() {
+        assert_eq!(standardize(0o2000000000000), Some(word(HALF, 256)));         // 0.5
+        assert_eq!(standardize(0o4000000000000), Some(word(-2*HALF, 256)));      // -1.0
+        assert_eq!(standardize(0o6000000000000), Some(word(-2*HALF, 255)));      // -0.5
+        assert_eq!(standardize(1), Some(word(HALF, 256 - 37)));                  // 2^-38
+        assert_eq!(standardize(0), Some(0));
+        assert_eq!(value(word(HALF, 256 + 3)), 4.0);
+        assert_eq!(add(word(1, 256), 0), Some(word(HALF, 256 - 28)));             // unstandard operand
+    }
+
+    #[test]
+    fn arithmetic() {
+        let one = word(HALF, 257);
+        let three = word(3*HALF/2, 258);
+        assert_eq!(add(one, one), Some(word(HALF, 258)));
+        assert_eq!(subtract(one, three), Some(word(-2*HALF, 257)));                // -2
+        assert_eq!(multiply(three, three), Some(word(9*HALF/8, 260)));             // 9
+        assert_eq!(subtract(one, one), Some(0));
+        assert_eq!(multiply(one, 0), Some(0));
+    }
+
+    #[test]
+    fn rounding() {
+        // 1/3 rounds down, 5/3 rounds up
+        let one = word(HALF, 257);
+        let three = word(3*HALF/2, 258);
+        let five = word(5*HALF/4, 259);
+        assert_eq!(divide(one, three), Some(word(0o2525252525, 255)));
+        assert_eq!(divide(five, three), Some(word(0o3252525253, 257)));
+        assert_eq!(divide(word(-2*HALF, 256), three), Some(word(-0o2525252525, 255)));
+        assert_eq!(divide(five, word(-3*HALF/2, 258)), Some(word(-0o3252525253, 257)));
+
+        // Half a unit rounds up; a little less rounds down
+        let big = word(HALF, 256);
+        let tiny = word(HALF, 256 - 29);
+        assert_eq!(add(big, tiny), Some(word(HALF + 1, 256)));
+        assert_eq!(add(big, word(HALF - 1, 256 - 29)), Some(big));
+        assert_eq!(add(add(big, tiny).unwrap(), word(-HALF, 256 - 200)), Some(word(HALF + 1, 256)));
+        assert_eq!(add(big, word(-HALF, 256 - 200)), Some(big));             // rounds up to 1/2
+    }
+
+    #[test]
+    fn overflow_and_underflow() {
+        let big = word(HALF, MAX_EXPONENT);
+        assert_eq!(add(big, big), None);
+        assert_eq!(multiply(big, big), None);
+        assert_eq!(divide(big, 0), None);
+        let small = word(HALF, 1);
+        assert_eq!(multiply(small, small), Some(0));
+    }
+}
@@ -0,0 +1,50 @@
+/***********************************************************************
+* simple-tokio/src/server/initial_instructions.rs
+*   Module "initial_instructions" for the 503's hard-wired bootstrap
+*   loader, which reads a binary program from the paper tape reader.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* The loader occupies locations 0-3:
+*       0:  26 4 : 06 0         n4' = 0; A' = 0
+*       1:  22 4 / 16 3         n4' = n4+1; store A at 3+n4, A' = 0
+*       2:  55 5 : 71 0         A' = A*2^5; A' = A | tape character
+*       3:  43 1 : 40 2         if overflow, jump to 1; else jump to 2
+*
+* Each word on tape is eight 5-bit characters, the first of which has
+* its top bit set as a marker. Assembly of a word is complete when the
+* marker shifts out of A and sets overflow. Leading blank tape is
+* ignored. The first word read replaces the counter in location 4,
+* setting the load address for the words that follow. A program is
+* entered by starting the loader again on a count word whose function
+* bits, once added to "16 3", turn it into a jump to the entry point.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+***********************************************************************/
+
+use super::processor::{instruction, word};
+use super::store::{Store, StoreError};
+
+pub const SIZE: usize = 4;
+
+pub fn words() -> [u64; SIZE] {
+    [
+        word(instruction(0o26, 4), false, instruction(0o06, 0)),
+        word(instruction(0o22, 4), true,  instruction(0o16, 3)),
+        word(instruction(0o55, 5), false, instruction(0o71, 0)),
+        word(instruction(0o43, 1), false, instruction(0o40, 2))
+    ]
+}
+
+pub fn load(store: &mut Store) -> Result<(), StoreError> {
+    /* Places the Initial Instructions into locations 0-3 */
+
+    for (addr, w) in words().iter().enumerate() {
+        store.poke(addr as u64, *w)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,466 @@
+/***********************************************************************
+* simple-tokio/src/server/line_printer.rs
+*   Module "line_printer" for the 503 line printer. Printed lines are
+*   written to a host plain-text file and to a paginated listing file
+*   that shows each sheet of listing paper.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Characters arrive in 8-hole code and are buffered until the line is
+* printed. LF prints and spaces one line, CR prints without spacing
+* (overprint), and FF prints and throws to the top of the form.
+*
+* The 72 control order prints the buffer and then moves the paper:
+*       0-63        space that many lines (0 = overprint)
+*       0o100+c     skip to the next line punched in channel c (1-12)
+*                   of the vertical format tape
+* Channel 1 marks the top of form. The plain-text file has a newline
+* for each line of paper movement and a form feed at each new sheet.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Implement the Device trait.
+*   Report when the device will next be ready.
+*   Save and restore the device state in snapshots.
+*   Stop the printer on CLEAR.
+*   Name the listing by appending ".lst", so it never replaces the text file.
+*   Discard output printed after the snapshot when restoring one.
+*   Flush the output through the Device trait, for snapshot restores.
+*   Refuse a snapshot whose form, sheet and line do not agree.
+*   Take the file names sent by clients within the media directory.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use crate::telecode;
+use super::device::{CommandResult, Device};
+use super::media;
+use super::register::{EmulationClock, EmulationTick, FlipFlop};
+use super::snapshot::{self, Snapshot, SnapshotResult};
+
+pub const PRINT_PERIOD: EmulationTick = 60.0/600.0;    // sec, 600 lines/min
+pub const SLEW_PERIOD: EmulationTick = 0.005;          // sec per line of paper movement
+pub const PAGE_LINES: usize = 66;                       // 11-inch sheet at 6 lines/inch
+pub const LINE_WIDTH: usize = 120;                      // print positions
+pub const TOP_OF_FORM: u8 = 1;                          // format tape channel
+
+const CHANNEL_ORDER: u64 = 0o100;
+
+// Status word bits returned by the 77 function
+pub const STATUS_MOUNTED: u64 = 1;
+pub const STATUS_TOP_OF_FORM: u64 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Advance {
+    Lines(usize),                       // space n lines, 0 to overprint
+    Channel(u8)                         // skip to a format tape channel
+}
+
+fn listing_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".lst");
+    PathBuf::from(name)
+}
+
+pub struct LinePrinter {
+    clock: Arc<EmulationClock>,
+    name: String,
+    text: Option<BufWriter<File>>,
+    listing: Option<BufWriter<File>>,
+    buffer: String,                     // line being assembled
+    page: Vec<String>,                  // image of the current sheet
+    line: usize,                        // current line on the sheet
+    sheets: usize,                      // sheets written to the listing
+    format: Vec<u16>,                   // channel bits for each line of the sheet
+    lines: usize,                       // lines printed
+    next_ready: EmulationTick,
+    pub busy_ff: FlipFlop
+}
+
+impl LinePrinter {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        let mut format = vec![0_u16; PAGE_LINES];
+        format[0] = 1 << (TOP_OF_FORM - 1);
+
+        LinePrinter {
+            clock: clock.clone(),
+            name: String::new(),
+            text: None,
+            listing: None,
+            buffer: String::with_capacity(LINE_WIDTH),
+            page: vec![String::new(); PAGE_LINES],
+            line: 0,
+            sheets: 0,
+            format,
+            lines: 0,
+            next_ready: 0.0,
+            busy_ff: FlipFlop::new(clock)
+        }
+    }
+
+    pub fn mount<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+        /* Loads fresh paper, creating the plain-text file at path and the
+        listing file beside it, named by appending ".lst" to path */
+
+        self.unload()?;
+        let path = path.as_ref();
+        self.text = Some(BufWriter::new(File::create(path)?));
+        self.listing = Some(BufWriter::new(File::create(listing_path(path))?));
+        self.name = path.display().to_string();
+        self.lines = 0;
+        self.sheets = 0;
+        self.line = 0;
+        Ok(())
+    }
+
+    pub fn unload(&mut self) -> std::io::Result<()> {
+        /* Tears off the paper, writing the partial sheet to the listing */
+
+        if self.listing.is_some() && self.page.iter().any(|l| !l.is_empty()) {
+            self.eject_sheet()?;
+        }
+
+        if let Some(mut file) = self.text.take() {
+            file.flush()?;
+        }
+
+        if let Some(mut file) = self.listing.take() {
+            file.flush()?;
+        }
+
+        self.name.clear();
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(file) = self.text.as_mut() {
+            file.flush()?;
+        }
+
+        match self.listing.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(())
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    pub fn set_format(&mut self, format: Vec<u16>) {
+        /* Replaces the vertical format tape; each entry holds the channel
+        bits (bit 0 = channel 1) punched for that line of the sheet */
+
+        if !format.is_empty() {
+            self.page.resize(format.len(), String::new());
+            self.line %= format.len();
+            self.format = format;
+        }
+    }
+
+    fn eject_sheet(&mut self) -> std::io::Result<()> {
+        /* Writes the current sheet to the listing file, framed by its
+        perforations and with the line number in the margin */
+
+        self.sheets += 1;
+        if let Some(file) = self.listing.as_mut() {
+            let rule = "-".repeat(LINE_WIDTH + 2);
+            writeln!(file, "     +{}+ Sheet {}", rule, self.sheets)?;
+            for (x, text) in self.page.iter().enumerate() {
+                writeln!(file, " {:3} | {:<width$} |", x + 1, text, width = LINE_WIDTH)?;
+            }
+            writeln!(file, "     +{}+\x0C", rule)?;
+        }
+
+        for text in self.page.iter_mut() {
+            text.clear();
+        }
+
+        Ok(())
+    }
+
+    fn distance(&self, advance: Advance) -> usize {
+        /* Returns the number of lines the paper must move. A skip to a
+        channel not on the format tape runs to the top of the next sheet */
+
+        match advance {
+            Advance::Lines(n) => n,
+            Advance::Channel(c) => {
+                let bit = 1_u16.checked_shl(c.max(1) as u32 - 1).unwrap_or(0);
+                let length = self.format.len();
+                (1..=length)
+                    .find(|&d| self.format[(self.line + d) % length] & bit != 0)
+                    .unwrap_or(length - self.line)
+            }
+        }
+    }
+
+    fn print(&mut self, advance: Advance) {
+        /* Prints the buffered line at the current position, moves the
+        paper, and advances the time at which the printer will next be
+        ready */
+
+        let now = self.clock.read();
+        let text = std::mem::take(&mut self.buffer);
+        let steps = self.distance(advance);
+
+        // Overprinted characters replace blanks on the sheet
+        let old: Vec<char> = self.page[self.line].chars().collect();
+        let new: Vec<char> = text.chars().collect();
+        self.page[self.line] = (0..old.len().max(new.len()))
+            .map(|x| match (old.get(x), new.get(x)) {
+                (Some(&' '), Some(&c)) | (None, Some(&c)) => c,
+                (Some(&c), _) => c,
+                (None, None) => ' '
+            })
+            .collect();
+
+        let mut out = text;
+        if steps == 0 {
+            out.push('\r');
+        }
+
+        for _ in 0..steps {
+            out.push('\n');
+            self.line += 1;
+            if self.line >= self.format.len() {
+                self.line = 0;
+                out.push('\x0C');
+                if let Err(e) = self.eject_sheet() {
+                    println!("line printer {} listing error: {}", self.name, e);
+                }
+            }
+        }
+
+        if let Some(file) = self.text.as_mut() {
+            if let Err(e) = file.write_all(out.as_bytes()) {
+                println!("line printer {} write error: {}", self.name, e);
+            }
+        }
+
+        self.lines += 1;
+        self.next_ready = now + PRINT_PERIOD + SLEW_PERIOD*steps as f64;
+        self.busy_ff.set(true);
+    }
+
+    pub fn write_char(&mut self, ch: u8) -> bool {
+        /* Buffers one 8-hole character, printing the line on LF, CR or FF.
+        Returns false if the printer is still busy with the previous line */
+
+        if self.clock.read() < self.next_ready {
+            return false;
+        }
+
+        match telecode::decode8(ch) {
+            Ok(Some('\n')) => self.print(Advance::Lines(1)),
+            Ok(Some('\r')) => self.print(Advance::Lines(0)),
+            Ok(Some('\x0C')) => self.print(Advance::Channel(TOP_OF_FORM)),
+            Ok(Some(c)) if !c.is_control() => {
+                if self.buffer.chars().count() < LINE_WIDTH {
+                    self.buffer.push(c);
+                }
+            }
+            Ok(_) => {}
+            Err(_) => {
+                if self.buffer.chars().count() < LINE_WIDTH {
+                    self.buffer.push('?');
+                }
+            }
+        }
+
+        true
+    }
+
+    pub fn control(&mut self, order: u64) -> bool {
+        /* Prints the buffered line and moves the paper as the order directs */
+
+        if self.clock.read() < self.next_ready {
+            false
+        } else {
+            let advance = if order & CHANNEL_ORDER != 0 {
+                Advance::Channel((order & 0o17) as u8)
+            } else {
+                Advance::Lines((order & 0o77) as usize)
+            };
+
+            self.print(advance);
+            true
+        }
+    }
+
+    pub fn status(&self) -> u64 {
+        let mut status = 0;
+        if self.text.is_some() {
+            status |= STATUS_MOUNTED;
+        }
+
+        if self.format[self.line] & (1 << (TOP_OF_FORM - 1)) != 0 {
+            status |= STATUS_TOP_OF_FORM;
+        }
+
+        status
+    }
+
+    pub fn update_lamps(&mut self) {
+        let moving = self.clock.read() < self.next_ready;
+        if moving != self.busy_ff.read() {
+            self.busy_ff.set(moving);
+        } else {
+            self.busy_ff.update_glow(0.0);
+        }
+    }
+} // impl LinePrinter
+
+impl Device for LinePrinter {
+
+    fn name(&self) -> &str {
+        "PRINTER"
+    }
+
+    fn write_char(&mut self, ch: u8) -> bool {
+        LinePrinter::write_char(self, ch)
+    }
+
+    fn control(&mut self, order: u64) -> bool {
+        LinePrinter::control(self, order)
+    }
+
+    fn status(&self) -> u64 {
+        LinePrinter::status(self)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        Some(self.next_ready)
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], media: &Path) -> Option<CommandResult> {
+        match code {
+            "LPMNT" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|name: String| media::resolve(media, &name)
+                        .and_then(|path| self.mount(path)).map_err(Into::into))),
+            "LPUNL" => Some(self.unload().map_err(Into::into)),
+            _ => None
+        }
+    }
+
+    fn clear(&mut self) {
+        self.next_ready = 0.0;
+        self.busy_ff.set(false);
+    }
+
+    fn reset(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn power(&mut self, _on: bool) {
+        if let Err(e) = self.flush() {
+            println!("Error flushing line printer: {}", e);
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        LinePrinter::flush(self)
+    }
+
+    fn update_lamps(&mut self) {
+        LinePrinter::update_lamps(self);
+    }
+
+    fn glow(&self) -> f32 {
+        *self.busy_ff.read_glow()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for LinePrinter
+
+impl Snapshot for LinePrinter {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        let length = |file: &Option<BufWriter<File>>| file.as_ref().map_or(Ok(0), snapshot::written);
+        serialize_into(&mut *out, &(&self.name, &self.buffer, &self.page, self.line, self.sheets,
+                &self.format, self.lines, self.next_ready))?;
+        serialize_into(&mut *out, &(length(&self.text)?, length(&self.listing)?))?;
+        self.busy_ff.save(out)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (name, buffer, page, line, sheets, format, lines, next_ready):
+                (String, String, Vec<String>, usize, usize, Vec<u16>, usize, EmulationTick) =
+                deserialize_from(&mut *input)?;
+        let (text_length, listing_length): (u64, u64) = deserialize_from(&mut *input)?;
+        if format.is_empty() || page.len() != format.len() || line >= page.len() {
+            return Err(snapshot::mismatch(format!(
+                    "snapshot printer has a {}-line form, {} lines of sheet and line {}",
+                    format.len(), page.len(), line)));
+        }
+
+        self.flush()?;
+        self.text = None;
+        self.listing = None;
+        if !name.is_empty() {
+            // Carry on printing where the files stood at the snapshot
+            let path = Path::new(&name);
+            self.text = Some(BufWriter::new(snapshot::reopen(path, text_length)?));
+            self.listing = Some(BufWriter::new(snapshot::reopen(listing_path(path), listing_length)?));
+        }
+
+        self.name = name;
+        self.buffer = buffer;
+        self.page = page;
+        self.line = line;
+        self.sheets = sheets;
+        self.format = format;
+        self.lines = lines;
+        self.next_ready = next_ready;
+        self.busy_ff.restore(input)
+    }
+} // impl Snapshot for LinePrinter
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn restored(printer: &LinePrinter) -> SnapshotResult {
+        let mut image = Vec::new();
+        printer.save(&mut image)?;
+        LinePrinter::new(Arc::new(EmulationClock::new(0.0))).restore(&mut image.as_slice())
+    }
+
+    #[test]
+    fn snapshot_form_checked() {
+        let mut printer = LinePrinter::new(Arc::new(EmulationClock::new(0.0)));
+        printer.line = PAGE_LINES - 1;
+        assert!(restored(&printer).is_ok());
+
+        printer.line = PAGE_LINES;
+        assert!(restored(&printer).is_err());
+        printer.line = 0;
+        printer.page.pop();
+        assert!(restored(&printer).is_err());
+        printer.format.clear();
+        printer.page.clear();
+        assert!(restored(&printer).is_err());
+    }
+}
@@ -0,0 +1,704 @@
+/***********************************************************************
+* simple-tokio/src/server/magnetic_tape.rs
+*   Module "magnetic_tape" for the 503 magnetic tape controller and its
+*   tape handlers. Each handler mounts a host tape-image file.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Tape images use the common ".tap" layout: each block is a 32-bit
+* little-endian byte count, the data, and the byte count again; a tape
+* mark is a single zero count. A 503 word occupies 5 bytes of a block,
+* least-significant byte first. The image is held in memory and written
+* back to the host file when the tape is unloaded or flushed.
+*
+* Blocks are read and written with the 75/76 block transfers, with the
+* handler number in the low 3 bits of the device address. The 72
+* control order selects the handler in bits 0-2 and the function in
+* bits 3-5:
+*       0 rewind                    1 skip forward one block
+*       2 backspace one block       3 write a tape mark
+*       4 skip forward past a tape mark
+*       5 backspace past a tape mark
+* There are eight handlers, one for each handler number. An order to a
+* handler with no tape mounted waits until the operator mounts one. The
+* 77 status word has 4 bits for each handler, handler 0 lowest.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Implement the Device trait.
+*   Report when a waiting handler will next be ready.
+*   Save and restore the device state in snapshots.
+*   Stop all tape motion on CLEAR.
+*   Provide all eight handlers the 3-bit handler number can select.
+*   Flush the output through the Device trait, for snapshot restores.
+*   Save the number of handlers in snapshots, and check it on restore.
+*   Test the tape motion orders and the tape image.
+*   Take the file names sent by clients within the media directory.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use super::device::{CommandResult, Device};
+use super::media;
+use super::processor::StopReason;
+use super::register::{EmulationClock, EmulationTick, FlipFlop};
+use super::snapshot::{self, Snapshot, SnapshotResult};
+use super::store::{Store, StoreError, WORD_MASK};
+
+pub const HANDLERS: usize = 8;                          // one for each 3-bit handler number
+pub const WORD_BYTES: usize = 5;
+
+pub const SPEED: f64 = 75.0;                            // inches/sec
+pub const REWIND_SPEED: f64 = 300.0;                    // inches/sec
+pub const DENSITY: f64 = 556.0;                         // bytes/inch
+pub const START_TIME: EmulationTick = 0.005;            // sec to start and stop the tape
+pub const GAP_BYTES: usize = 420;                       // inter-block gap, 0.75 inch
+
+// Control order functions
+pub const REWIND: u64 = 0;
+pub const SKIP_BLOCK: u64 = 1;
+pub const BACKSPACE_BLOCK: u64 = 2;
+pub const WRITE_MARK: u64 = 3;
+pub const SKIP_FILE: u64 = 4;
+pub const BACKSPACE_FILE: u64 = 5;
+
+// Status bits for each handler, returned by the 77 function
+pub const STATUS_READY: u64 = 1;        // mounted and not moving
+pub const STATUS_LOAD_POINT: u64 = 2;   // at the beginning of tape
+pub const STATUS_TAPE_MARK: u64 = 4;    // last operation passed a tape mark
+pub const STATUS_END_OF_TAPE: u64 = 8;  // ran off the recorded end of tape
+pub const STATUS_BITS: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Block {
+    Mark,
+    Data(std::ops::Range<usize>)        // byte range of the data in the image
+}
+
+pub struct TapeHandler {
+    path: Option<PathBuf>,
+    image: Vec<u8>,
+    position: usize,                    // byte offset in the image
+    write_enabled: bool,
+    dirty: bool,
+    tape_mark: bool,
+    end_of_tape: bool,
+    next_ready: EmulationTick
+}
+
+impl TapeHandler {
+
+    fn new() -> Self {
+        TapeHandler {
+            path: None,
+            image: Vec::new(),
+            position: 0,
+            write_enabled: false,
+            dirty: false,
+            tape_mark: false,
+            end_of_tape: false,
+            next_ready: 0.0
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path.display().to_string(),
+            None => String::new()
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn count_at(&self, pos: usize) -> Option<usize> {
+        self.image.get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    }
+
+    fn next_block(&self) -> Option<(Block, usize)> {
+        /* Returns the block after the current position and the position
+        following it, or None at the end of the recorded tape */
+
+        let count = self.count_at(self.position)?;
+        if count == 0 {
+            Some((Block::Mark, self.position + 4))
+        } else {
+            let start = self.position + 4;
+            let end = start + count + 4;
+            if end > self.image.len() {
+                None
+            } else {
+                Some((Block::Data(start..start + count), end))
+            }
+        }
+    }
+
+    fn prev_block(&self) -> Option<(Block, usize)> {
+        /* Returns the block before the current position and the position
+        preceding it, or None at the load point */
+
+        if self.position < 4 {
+            return None;
+        }
+
+        let count = self.count_at(self.position - 4)?;
+        if count == 0 {
+            Some((Block::Mark, self.position - 4))
+        } else {
+            let start = self.position.checked_sub(count + 8)?;
+            Some((Block::Data(start + 4..start + 4 + count), start))
+        }
+    }
+
+    fn motion_time(bytes: usize) -> EmulationTick {
+        START_TIME + (bytes + GAP_BYTES) as f64/(SPEED*DENSITY)
+    }
+
+    fn append(&mut self, data: &[u8]) {
+        /* Writes a block or tape mark at the current position. As on a real
+        handler, anything recorded beyond it is lost */
+
+        self.image.truncate(self.position);
+        let count = (data.len() as u32).to_le_bytes();
+        self.image.extend_from_slice(&count);
+        if !data.is_empty() {
+            self.image.extend_from_slice(data);
+            self.image.extend_from_slice(&count);
+        }
+
+        self.position = self.image.len();
+        self.dirty = true;
+    }
+
+    fn save(&mut self) -> std::io::Result<()> {
+        if self.dirty {
+            if let Some(path) = &self.path {
+                fs::write(path, &self.image)?;
+            }
+
+            self.dirty = false;
+        }
+
+        Ok(())
+    }
+
+    fn status(&self, now: EmulationTick) -> u64 {
+        let mut status = 0;
+        if self.path.is_some() && now >= self.next_ready {
+            status |= STATUS_READY;
+        }
+
+        if self.position == 0 {
+            status |= STATUS_LOAD_POINT;
+        }
+
+        if self.tape_mark {
+            status |= STATUS_TAPE_MARK;
+        }
+
+        if self.end_of_tape {
+            status |= STATUS_END_OF_TAPE;
+        }
+
+        status
+    }
+} // impl TapeHandler
+
+
+pub struct MagneticTape {
+    clock: Arc<EmulationClock>,
+    handlers: Vec<TapeHandler>,
+    waiting: Option<usize>,             // handler that was last found not ready
+    pub transfer_ff: FlipFlop
+}
+
+impl MagneticTape {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        MagneticTape {
+            clock: clock.clone(),
+            handlers: (0..HANDLERS).map(|_| TapeHandler::new()).collect(),
+            waiting: None,
+            transfer_ff: FlipFlop::new(clock)
+        }
+    }
+
+    pub fn handler(&self, unit: usize) -> Option<&TapeHandler> {
+        self.handlers.get(unit)
+    }
+
+    pub fn mount<P: AsRef<Path>>(&mut self, unit: usize, path: P, write_enabled: bool) -> std::io::Result<()> {
+        /* Mounts a tape image at its load point. With the write ring in,
+        a missing file is created as a blank tape */
+
+        let handler = self.handlers.get_mut(unit).ok_or_else(|| std::io::Error::new(
+                std::io::ErrorKind::NotFound, format!("no tape handler {}", unit)))?;
+        let path = path.as_ref();
+        let (image, blank) = match fs::read(path) {
+            Ok(image) => (image, false),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && write_enabled => (Vec::new(), true),
+            Err(e) => return Err(e)
+        };
+
+        handler.save()?;
+        *handler = TapeHandler::new();
+        handler.path = Some(path.to_path_buf());
+        handler.image = image;
+        handler.write_enabled = write_enabled;
+        handler.dirty = blank;
+        Ok(())
+    }
+
+    pub fn unload(&mut self, unit: usize) -> std::io::Result<()> {
+        match self.handlers.get_mut(unit) {
+            Some(handler) => {
+                let result = handler.save();
+                *handler = TapeHandler::new();
+                result
+            }
+            None => Ok(())
+        }
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        for handler in self.handlers.iter_mut() {
+            handler.save()?;
+        }
+
+        Ok(())
+    }
+
+    fn ready(&mut self, unit: usize) -> Option<&mut TapeHandler> {
+        /* Returns the handler if it is mounted and not moving. The machine
+        waits on a handler that is not ready */
+
+        let now = self.clock.read();
+        let ready = matches!(self.handlers.get(unit),
+                Some(h) if h.path.is_some() && now >= h.next_ready);
+        self.waiting = if ready {None} else {Some(unit)};
+        if ready {self.handlers.get_mut(unit)} else {None}
+    }
+
+    pub fn ready_time(&self) -> Option<EmulationTick> {
+        /* Returns when the handler last found not ready will finish moving,
+        or None if it has no tape mounted */
+
+        self.waiting.and_then(|unit| self.handlers.get(unit))
+            .filter(|h| h.path.is_some())
+            .map(|h| h.next_ready)
+    }
+
+    fn start(&mut self, unit: usize, period: EmulationTick) {
+        let now = self.clock.read();
+        self.handlers[unit].next_ready = now + period;
+        self.transfer_ff.set(true);
+    }
+
+    pub fn read_block(&mut self, unit: usize, count: usize, store: &mut Store, addr: u64) -> Result<bool, StoreError> {
+        /* Reads the next block into store at addr. A block longer than count
+        words is truncated; a tape mark transfers nothing */
+
+        let handler = match self.ready(unit) {
+            Some(h) => h,
+            None => return Ok(false)
+        };
+
+        handler.tape_mark = false;
+        let (words, bytes) = match handler.next_block() {
+            None => {
+                handler.end_of_tape = true;
+                (Vec::new(), 0)
+            }
+            Some((Block::Mark, next)) => {
+                handler.tape_mark = true;
+                handler.position = next;
+                (Vec::new(), 4)
+            }
+            Some((Block::Data(range), next)) => {
+                let words: Vec<u64> = handler.image[range.clone()].chunks(WORD_BYTES).take(count)
+                    .map(|c| c.iter().rev().fold(0, |w, &b| (w << 8) | b as u64) & WORD_MASK)
+                    .collect();
+                handler.position = next;
+                (words, range.len())
+            }
+        };
+
+        self.start(unit, TapeHandler::motion_time(bytes));
+        for (x, &word) in words.iter().enumerate() {
+            store.write(addr + x as u64, word)?;
+        }
+
+        Ok(true)
+    }
+
+    pub fn write_block(&mut self, unit: usize, count: usize, store: &mut Store, addr: u64) -> Result<bool, StoreError> {
+        /* Writes count words from store at addr as one block. Without the
+        write ring the handler moves no tape and signals end of tape */
+
+        let handler = match self.ready(unit) {
+            Some(h) => h,
+            None => return Ok(false)
+        };
+
+        handler.tape_mark = false;
+        if !handler.write_enabled {
+            handler.end_of_tape = true;
+            return Ok(true);
+        }
+
+        let mut data = Vec::with_capacity(count*WORD_BYTES);
+        for x in 0..count {
+            let word = store.read(addr + x as u64)?;
+            data.extend_from_slice(&word.to_le_bytes()[..WORD_BYTES]);
+        }
+
+        let handler = &mut self.handlers[unit];
+        handler.append(&data);
+        self.start(unit, TapeHandler::motion_time(data.len()));
+        Ok(true)
+    }
+
+    pub fn control(&mut self, order: u64) -> bool {
+        /* Executes a tape motion order, returning false while the addressed
+        handler is not ready */
+
+        let unit = (order & 0o7) as usize;
+        let handler = match self.ready(unit) {
+            Some(h) => h,
+            None => return false
+        };
+
+        handler.tape_mark = false;
+        handler.end_of_tape = false;
+        let start = handler.position;
+        match (order >> 3) & 0o7 {
+            REWIND => {
+                handler.position = 0;
+            }
+            SKIP_BLOCK => {
+                match handler.next_block() {
+                    Some((block, next)) => {
+                        handler.tape_mark = block == Block::Mark;
+                        handler.position = next;
+                    }
+                    None => handler.end_of_tape = true
+                }
+            }
+            BACKSPACE_BLOCK => {
+                if let Some((block, prev)) = handler.prev_block() {
+                    handler.tape_mark = block == Block::Mark;
+                    handler.position = prev;
+                }
+            }
+            WRITE_MARK => {
+                if handler.write_enabled {
+                    handler.append(&[]);
+                } else {
+                    handler.end_of_tape = true;
+                }
+            }
+            SKIP_FILE => {
+                loop {
+                    match handler.next_block() {
+                        Some((block, next)) => {
+                            handler.position = next;
+                            if block == Block::Mark {
+                                handler.tape_mark = true;
+                                break;
+                            }
+                        }
+                        None => {
+                            handler.end_of_tape = true;
+                            break;
+                        }
+                    }
+                }
+            }
+            BACKSPACE_FILE => {
+                while let Some((block, prev)) = handler.prev_block() {
+                    handler.position = prev;
+                    if block == Block::Mark {
+                        handler.tape_mark = true;
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let moved = (handler.position as isize - start as isize).unsigned_abs();
+        let period = if (order >> 3) & 0o7 == REWIND {
+            START_TIME + moved as f64/(REWIND_SPEED*DENSITY)
+        } else {
+            TapeHandler::motion_time(moved)
+        };
+
+        self.start(unit, period);
+        true
+    }
+
+    pub fn status(&self) -> u64 {
+        let now = self.clock.read();
+        self.handlers.iter().enumerate()
+            .fold(0, |status, (x, h)| status | (h.status(now) << (x as u32*STATUS_BITS)))
+    }
+
+    pub fn update_lamps(&mut self) {
+        let now = self.clock.read();
+        let moving = self.handlers.iter().any(|h| now < h.next_ready);
+        if moving != self.transfer_ff.read() {
+            self.transfer_ff.set(moving);
+        } else {
+            self.transfer_ff.update_glow(0.0);
+        }
+    }
+} // impl MagneticTape
+
+impl Device for MagneticTape {
+
+    fn name(&self) -> &str {
+        "MTAPE"
+    }
+
+    fn control(&mut self, order: u64) -> bool {
+        MagneticTape::control(self, order)
+    }
+
+    fn status(&self) -> u64 {
+        MagneticTape::status(self)
+    }
+
+    fn transfer(&mut self, output: bool, device_addr: u64, count: usize,
+            store: &mut Store, addr: u64) -> Result<bool, StopReason> {
+        let unit = (device_addr & 0o7) as usize;
+        let result = if output {
+            self.write_block(unit, count, store, addr)
+        } else {
+            self.read_block(unit, count, store, addr)
+        };
+
+        result.map_err(StopReason::from)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        MagneticTape::ready_time(self)
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], media: &Path) -> Option<CommandResult> {
+        match code {
+            "MTMNT" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|(unit, name, write_enabled): (usize, String, bool)|
+                        media::resolve(media, &name)
+                        .and_then(|path| self.mount(unit, path, write_enabled)).map_err(Into::into))),
+            "MTUNL" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|unit: usize| self.unload(unit).map_err(Into::into))),
+            _ => None
+        }
+    }
+
+    fn clear(&mut self) {
+        /* Stops every handler where it is; the tape has already been moved
+        to the end of the operation in progress */
+
+        for handler in self.handlers.iter_mut() {
+            handler.next_ready = 0.0;
+        }
+
+        self.waiting = None;
+        self.transfer_ff.set(false);
+    }
+
+    fn power(&mut self, _on: bool) {
+        if let Err(e) = self.flush() {
+            println!("Error flushing magnetic tapes: {}", e);
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        MagneticTape::flush(self)
+    }
+
+    fn update_lamps(&mut self) {
+        MagneticTape::update_lamps(self);
+    }
+
+    fn glow(&self) -> f32 {
+        *self.transfer_ff.read_glow()
+    }
+
+    fn transfer_glow(&self) -> f32 {
+        *self.transfer_ff.read_glow()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for MagneticTape
+
+impl Snapshot for MagneticTape {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        /* Saves each handler with its whole tape image, so the restored
+        tapes match the machine state whatever has since been written */
+
+        serialize_into(&mut *out, &self.handlers.len())?;
+        for h in self.handlers.iter() {
+            serialize_into(&mut *out, &(&h.path, &h.image, h.position, h.write_enabled,
+                    h.dirty, h.tape_mark, h.end_of_tape, h.next_ready))?;
+        }
+
+        self.transfer_ff.save(out)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let count: usize = deserialize_from(&mut *input)?;
+        if count != self.handlers.len() {
+            return Err(snapshot::mismatch(format!("snapshot has {} tape handlers, this machine {}",
+                    count, self.handlers.len())));
+        }
+
+        self.flush()?;
+        for h in self.handlers.iter_mut() {
+            let (path, image, position, write_enabled, dirty, tape_mark, end_of_tape, next_ready) =
+                    deserialize_from(&mut *input)?;
+            *h = TapeHandler {path, image, position, write_enabled, dirty, tape_mark, end_of_tape, next_ready};
+        }
+
+        self.waiting = None;
+        self.transfer_ff.restore(input)
+    }
+} // impl Snapshot for MagneticTape
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT: usize = 2;
+
+    fn order(function: u64) -> u64 {
+        function << 3 | UNIT as u64
+    }
+
+    fn status(tape: &MagneticTape) -> u64 {
+        (tape.status() >> (UNIT as u32*STATUS_BITS)) & 0o17
+    }
+
+    fn settle(clock: &EmulationClock, tape: &MagneticTape) {
+        /* Lets the handler finish its last operation */
+
+        let ready = tape.handlers[UNIT].next_ready;
+        let now = clock.read();
+        if ready > now {
+            clock.advance(ready - now);
+        }
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let path = std::env::temp_dir().join(format!("mtape-{}-blocks.tap", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut tape = MagneticTape::new(clock.clone());
+        let mut store = Store::new(1, clock.clone());
+        for (addr, &word) in [0o1, 0o2, WORD_MASK].iter().enumerate() {
+            store.poke(addr as u64, word).unwrap();
+        }
+
+        // Write two blocks, a tape mark and a third block on a blank tape
+        tape.mount(UNIT, &path, true).unwrap();
+        assert_eq!(status(&tape), STATUS_READY | STATUS_LOAD_POINT);
+        assert_eq!(tape.write_block(UNIT, 2, &mut store, 0), Ok(true));
+        assert_eq!(tape.write_block(UNIT, 1, &mut store, 2), Ok(false));
+        assert_eq!(tape.ready_time(), Some(tape.handlers[UNIT].next_ready));
+        settle(&clock, &tape);
+        assert_eq!(tape.write_block(UNIT, 1, &mut store, 2), Ok(true));
+        settle(&clock, &tape);
+        assert!(tape.control(order(WRITE_MARK)));
+        settle(&clock, &tape);
+        assert_eq!(tape.write_block(UNIT, 1, &mut store, 0), Ok(true));
+        tape.unload(UNIT).unwrap();
+        assert_eq!(status(&tape), STATUS_LOAD_POINT);
+
+        let image = fs::read(&path).unwrap();
+        assert_eq!(image.len(), 18 + 13 + 4 + 13);
+        assert_eq!(image[..9], [10, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(image[14..18], [10, 0, 0, 0]);
+        assert_eq!(image[31..35], [0, 0, 0, 0]);
+
+        // Read it back without the write ring
+        tape.mount(UNIT, &path, false).unwrap();
+        assert_eq!(tape.read_block(UNIT, 5, &mut store, 100), Ok(true));
+        assert_eq!((store.peek(100), store.peek(101)), (Ok(0o1), Ok(0o2)));
+        settle(&clock, &tape);
+        assert!(tape.control(order(SKIP_BLOCK)));
+        settle(&clock, &tape);
+        assert_eq!(tape.read_block(UNIT, 5, &mut store, 102), Ok(true));
+        assert_eq!(status(&tape), STATUS_TAPE_MARK);
+        settle(&clock, &tape);
+        assert_eq!(status(&tape), STATUS_READY | STATUS_TAPE_MARK);
+        assert_eq!(store.peek(102), Ok(0));
+
+        // Backspace over the tape mark and the second block
+        assert!(tape.control(order(BACKSPACE_BLOCK)));
+        settle(&clock, &tape);
+        assert_eq!(status(&tape), STATUS_READY | STATUS_TAPE_MARK);
+        assert!(tape.control(order(BACKSPACE_BLOCK)));
+        settle(&clock, &tape);
+        assert_eq!(tape.read_block(UNIT, 5, &mut store, 110), Ok(true));
+        assert_eq!(store.peek(110), Ok(WORD_MASK));
+        settle(&clock, &tape);
+
+        // Skip the file to the third block, then run off the end
+        assert!(tape.control(order(SKIP_FILE)));
+        settle(&clock, &tape);
+        assert_eq!(status(&tape), STATUS_READY | STATUS_TAPE_MARK);
+        assert_eq!(tape.read_block(UNIT, 5, &mut store, 120), Ok(true));
+        assert_eq!(store.peek(120), Ok(0o1));
+        settle(&clock, &tape);
+        assert_eq!(tape.read_block(UNIT, 5, &mut store, 121), Ok(true));
+        settle(&clock, &tape);
+        assert_eq!(status(&tape), STATUS_READY | STATUS_END_OF_TAPE);
+
+        // Rewind, and read the first block into a shorter area
+        assert!(tape.control(order(REWIND)));
+        settle(&clock, &tape);
+        assert_eq!(status(&tape), STATUS_READY | STATUS_LOAD_POINT);
+        assert_eq!(tape.read_block(UNIT, 1, &mut store, 130), Ok(true));
+        assert_eq!((store.peek(130), store.peek(131)), (Ok(0o1), Ok(0)));
+        settle(&clock, &tape);
+
+        // Without the write ring nothing is written
+        assert_eq!(tape.write_block(UNIT, 1, &mut store, 0), Ok(true));
+        assert_eq!(status(&tape), STATUS_READY | STATUS_END_OF_TAPE);
+        tape.unload(UNIT).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), image);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unmounted_handler_waits() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut tape = MagneticTape::new(clock.clone());
+        let mut store = Store::new(1, clock);
+        assert!(!tape.control(order(REWIND)));
+        assert_eq!(tape.read_block(UNIT, 1, &mut store, 0), Ok(false));
+        assert_eq!(tape.ready_time(), None);
+    }
+}
@@ -0,0 +1,53 @@
+/***********************************************************************
+* simple-tokio/src/server/media.rs
+*   Module "media" turns the file names sent by clients into host paths
+*   within the directories the server was started with.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Any client that can reach the server's socket can mount tapes, export
+* plots and save or restore snapshots, so the names it sends must not
+* reach the rest of the host. A name is taken relative to the media
+* directory (tapes, listings and plots) or the snapshot directory, and
+* is refused if it is absolute or climbs out of the directory with "..".
+* Names given on the server's own command line, and by the run mode,
+* are host paths and are not restricted.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+***********************************************************************/
+
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+pub fn resolve(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    /* Returns the path of name within dir, or an error if name is empty,
+    absolute, or leaves dir */
+
+    let relative = Path::new(name);
+    let inside = relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if name.is_empty() || !inside {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied,
+                format!("file name {} is not within {}", name, dir.display())));
+    }
+
+    Ok(dir.join(relative))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_kept_within_directory() {
+        let dir = Path::new("media");
+        assert_eq!(resolve(dir, "tape.rt").unwrap(), dir.join("tape.rt"));
+        assert_eq!(resolve(dir, "jobs/./tape.rt").unwrap(), dir.join("jobs/./tape.rt"));
+        for name in &["", "/etc/passwd", "../tape.rt", "jobs/../../tape.rt", ".."] {
+            assert_eq!(resolve(dir, name).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        }
+    }
+}
@@ -0,0 +1,464 @@
+/***********************************************************************
+* simple-tokio/src/server/monitor.rs
+*   Module "monitor" is the server's debugging console: it examines and
+*   deposits store words, shows the registers, sets breakpoints and
+*   watchpoints, and steps or continues the processor.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Commands are read from the server's stdin, or arrive as MONTR
+* messages on a debug connection (see "simple-tokio monitor"). Store
+* addresses are decimal and words are octal. Commands:
+*       e addr [count]      examine store words
+*       d addr word...      deposit words starting at addr
+*       r                   show the registers
+*       b [addr]            set a breakpoint, or list them
+*       bc addr             clear a breakpoint
+*       w [addr]            set a watchpoint, or list them
+*       wc addr             clear a watchpoint
+*       s [n]               step n instructions (half-words), at most MAX_STEPS
+*       sw [n]              step n words, at most MAX_STEPS
+*       c [addr]            continue, at the first instruction of addr if given
+*       h                   halt
+*       t file [first-last] write an instruction trace to file, for SCR in the range
+*       t                   stop the trace
+*       l file              load an absolute binary tape image into the
+*                           store, and set SCR to its entry point
+* Files are named within the server's media directory (see media.rs).
+* A breakpoint stops the processor before it executes an instruction
+* of that word, unless it is going on to the second instruction from
+* the first. A watchpoint stops it after an instruction that changes
+* the watched word. Stops are reported on the server's stdout as they
+* happen, and to a debug connection with the reply to its next command;
+* only the most recent MAX_NOTICES are kept for it. A step command ends
+* early at a stop that happens during the steps. The server is held for
+* the whole of a command, so steps are limited to keep it responsive,
+* and, like continue, they need the power on.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Add the trace command.
+*   Show words through the disassembler.
+*   Add the load command.
+*   End a step command at a stop it causes, not at one already pending.
+*   Load absolute binary tapes only.
+*   Limit the steps of one command, and refuse to step with the power off.
+*   Check all the words of a deposit before storing any.
+*   Name the trace and load files within the media directory.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::{ServerState, record_trace};
+use super::disasm;
+use super::loader::{self, Loader};
+use super::media;
+use super::processor::{Processor, Step};
+use super::store::{Address, Store, Word, WORD_MASK};
+use super::trace::{self, Tracer};
+
+pub const MAX_EXAMINE: u64 = 256;       // words listed by one examine command
+pub const MAX_NOTICES: usize = 100;     // unreported stops kept for a debug connection
+pub const MAX_STEPS: u64 = 10000;       // instructions or words stepped by one command
+
+const HELP: &str = "\
+e addr [count]   examine      d addr word...   deposit      r   registers
+b [addr]         breakpoint   bc addr          clear break
+w [addr]         watchpoint   wc addr          clear watch
+s [n]            step         sw [n]           step words
+c [addr]         continue     h                halt
+t [file [first-last]]         trace to file, or stop the trace
+l file                        load an absolute binary tape into the store
+addresses are decimal, words octal";
+
+#[derive(Default)]
+pub struct Monitor {
+    breakpoints: BTreeSet<Address>,
+    watchpoints: BTreeMap<Address, Word>,   // address -> value last seen
+    last_addr: Option<Address>,             // word of the instruction last executed
+    resume: bool,                           // pass over a breakpoint at the current word
+    notices: Vec<String>,                   // stops not yet reported to a client
+    stops: u64                              // stops noticed so far
+}
+
+impl Monitor {
+
+    pub fn new() -> Self {
+        Monitor::default()
+    }
+
+    pub fn at_breakpoint(&mut self, processor: &Processor) -> bool {
+        /* Returns true if the processor is about to enter a word with a
+        breakpoint, and should stop before executing it */
+
+        let addr = processor.scr.read();
+        let entering = !processor.is_second_half() || self.last_addr != Some(addr);
+        let resume = std::mem::replace(&mut self.resume, false);
+        if entering && !resume && self.breakpoints.contains(&addr) {
+            self.notice(format!("breakpoint at {}", addr));
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn executed(&mut self, addr: Address, store: &Store) -> bool {
+        /* Records that an instruction of word addr has been executed and
+        returns true if it changed a watched word */
+
+        self.last_addr = Some(addr);
+        let mut hit = false;
+        let mut changes = Vec::new();
+        for (&watch, last) in self.watchpoints.iter_mut() {
+            let word = store.peek(watch).unwrap_or(0);
+            if word != *last {
+                changes.push(format!("watchpoint {} changed {:013o} to {:013o} by word {}",
+                        watch, *last, word, addr));
+                *last = word;
+                hit = true;
+            }
+        }
+
+        for change in changes {
+            self.notice(change);
+        }
+
+        hit
+    }
+
+    fn notice(&mut self, text: String) {
+        println!("monitor: {}", text);
+        self.stops += 1;
+        if self.notices.len() >= MAX_NOTICES {
+            self.notices.remove(0);
+        }
+        self.notices.push(text);
+    }
+} // impl Monitor
+
+
+fn parse_address(arg: Option<&str>, store: &Store) -> Result<Address, String> {
+    let text = arg.ok_or("address required")?;
+    match text.parse::<Address>() {
+        Ok(addr) if (addr as usize) < store.size() => Ok(addr),
+        Ok(addr) => Err(format!("address {} is outside the store", addr)),
+        Err(_) => Err(format!("bad address {}", text))
+    }
+}
+
+fn parse_count(arg: Option<&str>) -> Result<u64, String> {
+    match arg {
+        Some(text) => text.parse().map_err(|_| format!("bad count {}", text)),
+        None => Ok(1)
+    }
+}
+
+fn parse_word(text: &str) -> Result<Word, String> {
+    match Word::from_str_radix(text, 8) {
+        Ok(word) if word <= WORD_MASK => Ok(word),
+        _ => Err(format!("bad octal word {}", text))
+    }
+}
+
+fn registers(state: &ServerState) -> String {
+    let p = &state.processor;
+    format!("SCR {}{}  A {:013o}  AR {:013o}  IR {:013o}  OV {}  {}  time {:.6}",
+            p.scr.read(), if p.is_second_half() {".2"} else {".1"},
+            p.a_reg.read(), p.ar_reg.read(), p.ir.read(), p.overflow_ff.read() as u8,
+            if p.running {"running"} else {"stopped"}, state.eclock.read())
+}
+
+fn step(state: &mut ServerState) -> Result<(), String> {
+    /* Executes one instruction, carrying the emulation clock across any
+    wait for a device that will become ready on its own */
+
+    let addr = state.processor.scr.read();
+    loop {
+        let step = state.processor.step(&mut state.store, &mut state.bus);
+        record_trace(state);
+        match step {
+            Step::Continue => break,
+            Step::Wait => match state.bus.ready_time() {
+                Some(ready) => {
+                    let now = state.eclock.read();
+                    if ready > now {
+                        state.eclock.advance(ready - now);
+                    }
+                }
+                None => return Err("waiting for input".to_string())
+            },
+            Step::Stop(reason) => {
+                state.last_stop = Some(reason);
+                return Err(format!("processor stopped {:?}", reason));
+            }
+        }
+    }
+
+    state.monitor.executed(addr, &state.store);
+    Ok(())
+}
+
+fn step_word(state: &mut ServerState) -> Result<(), String> {
+    /* Executes instructions until the processor leaves the current word */
+
+    let addr = state.processor.scr.read();
+    step(state)?;
+    while state.processor.scr.read() == addr && state.processor.is_second_half() {
+        step(state)?;
+    }
+
+    Ok(())
+}
+
+fn execute(state: &mut ServerState, line: &str) -> Result<String, String> {
+    let mut args = line.split_whitespace();
+    let mut out = String::new();
+    match args.next() {
+        None => {}
+        Some("e") => {
+            let addr = parse_address(args.next(), &state.store)?;
+            let count = parse_count(args.next())?.min(MAX_EXAMINE);
+            for a in addr..(addr + count).min(state.store.size() as Address) {
+                let word = state.store.peek(a).map_err(|e| e.to_string())?;
+                writeln!(out, "{:5}  {}", a, disasm::format_word(word)).ok();
+            }
+        }
+        Some("d") => {
+            // Check every word before depositing any, so that a bad
+            // command leaves the store as it was
+            let addr = parse_address(args.next(), &state.store)?;
+            let words = args.map(parse_word).collect::<Result<Vec<Word>, String>>()?;
+            let end = addr + words.len() as Address;
+            if end > state.store.size() as Address {
+                return Err(format!("words {}-{} run past the end of the store", addr, end - 1));
+            }
+            for (a, &word) in (addr..end).zip(words.iter()) {
+                state.store.poke(a, word).map_err(|e| e.to_string())?;
+            }
+            for (watch, last) in state.monitor.watchpoints.iter_mut() {
+                *last = state.store.peek(*watch).unwrap_or(0);
+            }
+            writeln!(out, "{} words deposited at {}", words.len(), addr).ok();
+        }
+        Some("r") => {
+            writeln!(out, "{}", registers(state)).ok();
+        }
+        Some("b") => match args.next() {
+            Some(text) => {
+                let addr = parse_address(Some(text), &state.store)?;
+                state.monitor.breakpoints.insert(addr);
+            }
+            None => {
+                for addr in state.monitor.breakpoints.iter() {
+                    writeln!(out, "breakpoint {}", addr).ok();
+                }
+            }
+        },
+        Some("bc") => {
+            let addr = parse_address(args.next(), &state.store)?;
+            state.monitor.breakpoints.remove(&addr);
+        }
+        Some("w") => match args.next() {
+            Some(text) => {
+                let addr = parse_address(Some(text), &state.store)?;
+                let word = state.store.peek(addr).map_err(|e| e.to_string())?;
+                state.monitor.watchpoints.insert(addr, word);
+            }
+            None => {
+                for (addr, word) in state.monitor.watchpoints.iter() {
+                    writeln!(out, "watchpoint {}  {:013o}", addr, word).ok();
+                }
+            }
+        },
+        Some("wc") => {
+            let addr = parse_address(args.next(), &state.store)?;
+            state.monitor.watchpoints.remove(&addr);
+        }
+        Some(cmd @ "s") | Some(cmd @ "sw") => {
+            if !state.power_on {
+                return Err("power is off".to_string());
+            }
+            let count = parse_count(args.next())?.min(MAX_STEPS);
+            state.processor.running = false;
+            for _ in 0..count {
+                let seen = state.monitor.stops;
+                if cmd == "s" {step(state)?} else {step_word(state)?}
+                if state.monitor.stops != seen {
+                    break;
+                }
+            }
+            writeln!(out, "{}", registers(state)).ok();
+        }
+        Some("c") => {
+            if !state.power_on {
+                return Err("power is off".to_string());
+            }
+            if let Some(text) = args.next() {
+                let addr = parse_address(Some(text), &state.store)?;
+                state.processor.jump(addr, false);
+            }
+            state.monitor.resume = true;
+            state.last_stop = None;
+            state.processor.running = true;
+        }
+        Some("h") => {
+            state.processor.running = false;
+            writeln!(out, "{}", registers(state)).ok();
+        }
+        Some("t") => {
+            if let Some(mut tracer) = state.tracer.take() {
+                tracer.flush().map_err(|e| e.to_string())?;
+                writeln!(out, "trace stopped after {} instructions", tracer.records()).ok();
+            }
+            if let Some(path) = args.next() {
+                let range = match args.next() {
+                    Some(text) => Some(trace::parse_range(text).map_err(|e| e.to_string())?),
+                    None => None
+                };
+                let file = media::resolve(&state.media_dir, path).map_err(|e| e.to_string())?;
+                state.tracer = Some(Tracer::create(file, range).map_err(|e| e.to_string())?);
+                writeln!(out, "tracing to {}", path).ok();
+            }
+        }
+        Some("l") => {
+            let name = args.next().ok_or("file required")?;
+            let path = media::resolve(&state.media_dir, name).map_err(|e| e.to_string())?;
+            let mut loader = Loader::new();
+            loader::load_file(&mut loader, path, &mut state.store).map_err(|e| e.to_string())?;
+            writeln!(out, "{} words loaded", loader.words).ok();
+            if let Some((addr, second)) = loader.entry {
+                state.processor.running = false;
+                state.processor.jump(addr, second);
+                writeln!(out, "entry at {}{}", addr, if second {".2"} else {".1"}).ok();
+            }
+            for (watch, last) in state.monitor.watchpoints.iter_mut() {
+                *last = state.store.peek(*watch).unwrap_or(0);
+            }
+        }
+        Some("?") | Some("help") => {
+            writeln!(out, "{}", HELP).ok();
+        }
+        Some(cmd) => return Err(format!("unknown command {}, ? for help", cmd))
+    }
+
+    Ok(out)
+}
+
+pub fn command(state: &mut ServerState, line: &str) -> String {
+    /* Executes one monitor command, returning its output */
+
+    match execute(state, line) {
+        Ok(text) => text,
+        Err(e) => format!("{}\n", e)
+    }
+}
+
+pub fn take_notices(state: &mut ServerState) -> String {
+    /* Returns the stops not yet reported to a debug connection */
+
+    state.monitor.notices.drain(..)
+        .map(|text| format!("monitor: {}\n", text))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{new_state, run_slice};
+    use super::super::pacing::Speed;
+    use super::super::processor::{instruction, word, StopReason};
+
+    fn machine() -> ServerState {
+        // Word 0 counts word 10 up and goes on to word 1, which is a
+        // dynamic stop
+        let mut state = new_state(Speed::Unthrottled);
+        state.power_on = true;
+        state.store.poke(0, word(instruction(0o22, 10), false, instruction(0o40, 1))).unwrap();
+        state.store.poke(1, word(instruction(0o40, 1), false, instruction(0o40, 1))).unwrap();
+        state
+    }
+
+    #[test]
+    fn deposit_checked_first() {
+        let mut state = machine();
+        let last = state.store.size() as Address - 1;
+        state.store.poke(5, 0o7).unwrap();
+
+        assert_eq!(command(&mut state, "d 5 1 2 9"), "bad octal word 9\n");
+        assert_eq!(state.store.peek(5).unwrap(), 0o7);
+        assert!(command(&mut state, &format!("d {} 1 2", last)).contains("past the end"));
+        assert_eq!(state.store.peek(last).unwrap(), 0);
+
+        assert_eq!(command(&mut state, "d 5 1 2"), "2 words deposited at 5\n");
+        assert_eq!((state.store.peek(5).unwrap(), state.store.peek(6).unwrap()), (1, 2));
+    }
+
+    #[test]
+    fn breakpoint_stops_before_word() {
+        let mut state = machine();
+        command(&mut state, "b 1");
+        command(&mut state, "c 0");
+        run_slice(&mut state);
+        assert!(!state.processor.running);
+        assert_eq!((state.processor.scr.read(), state.processor.is_second_half()), (1, false));
+        assert_eq!(state.store.peek(10).unwrap(), 1);
+        assert_eq!(state.last_stop, None);
+        assert_eq!(take_notices(&mut state), "monitor: breakpoint at 1\n");
+
+        // Continuing passes over the breakpoint to the dynamic stop
+        command(&mut state, "c");
+        run_slice(&mut state);
+        assert_eq!(state.last_stop, Some(StopReason::DynamicStop(1)));
+        assert_eq!(take_notices(&mut state), "");
+    }
+
+    #[test]
+    fn watchpoint_stops_after_change() {
+        let mut state = machine();
+        command(&mut state, "w 10");
+        command(&mut state, "c 0");
+        run_slice(&mut state);
+        assert!(!state.processor.running);
+        assert_eq!((state.processor.scr.read(), state.processor.is_second_half()), (0, true));
+        assert_eq!(take_notices(&mut state),
+                "monitor: watchpoint 10 changed 0000000000000 to 0000000000001 by word 0\n");
+
+        // A deposit to the watched word is not a change by the program
+        command(&mut state, "d 10 5");
+        command(&mut state, "c");
+        run_slice(&mut state);
+        assert_eq!(state.last_stop, Some(StopReason::DynamicStop(1)));
+        assert_eq!(take_notices(&mut state), "");
+    }
+
+    #[test]
+    fn steps() {
+        let mut state = machine();
+        state.power_on = false;
+        assert_eq!(command(&mut state, "s"), "power is off\n");
+
+        state.power_on = true;
+        command(&mut state, "s");
+        assert_eq!((state.processor.scr.read(), state.processor.is_second_half()), (0, true));
+        command(&mut state, "s");
+        assert_eq!((state.processor.scr.read(), state.processor.is_second_half()), (1, false));
+
+        // Stepping words stops at the dynamic stop, not after the count
+        state.processor.jump(0, false);
+        let out = command(&mut state, "sw 5");
+        assert!(out.starts_with("processor stopped DynamicStop(1)"));
+        assert_eq!(state.store.peek(10).unwrap(), 2);
+
+        // A step that reaches a watchpoint ends the command there
+        state.processor.jump(0, false);
+        command(&mut state, "w 10");
+        command(&mut state, "s 5");
+        assert_eq!((state.processor.scr.read(), state.processor.is_second_half()), (0, true));
+    }
+}
@@ -0,0 +1,151 @@
+/***********************************************************************
+* simple-tokio/src/server/pacing.rs
+*   Module "pacing" keeps the emulation clock in step with host time at
+*   a selected speed ratio, and measures the ratio actually achieved.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* The pacer is anchored at a pair of host and emulated times. At a
+* ratio r, emulated time t should be reached at host time t/r after
+* the anchor; when the processor gets ahead of that, the server sleeps
+* for the difference. While the processor is idle, emulated time is
+* carried forward with host time instead. Changing the speed, or a
+* period with the power off, re-anchors the pacer so the processor
+* neither races to catch up nor stalls to fall back. Unthrottled, the
+* processor never sleeps, but idle periods still pass in real time.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::register::{EmulationClock, EmulationTick};
+
+pub const MEASURE_PERIOD: f64 = 0.5;    // sec of host time per ratio measurement
+pub const MAX_LAG: f64 = 0.25;          // sec the processor may fall behind before re-anchoring
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Speed {
+    Ratio(f64),                         // emulated seconds per host second; 1.0 is real time
+    Unthrottled
+}
+
+pub struct Pacer {
+    clock: Arc<EmulationClock>,
+    speed: Speed,
+    host_anchor: Instant,
+    emulated_anchor: EmulationTick,
+    host_sample: Instant,
+    emulated_sample: EmulationTick,
+    achieved: f64
+}
+
+impl Pacer {
+
+    pub fn new(clock: Arc<EmulationClock>, speed: Speed) -> Self {
+        let now = clock.read();
+        Pacer {
+            clock,
+            speed,
+            host_anchor: Instant::now(),
+            emulated_anchor: now,
+            host_sample: Instant::now(),
+            emulated_sample: now,
+            achieved: 0.0
+        }
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        self.anchor();
+    }
+
+    pub fn anchor(&mut self) {
+        /* Starts pacing afresh from the current host and emulated times */
+
+        self.host_anchor = Instant::now();
+        self.emulated_anchor = self.clock.read();
+    }
+
+    pub fn restart(&mut self) {
+        /* Anchors the pacer and starts a new measurement period, after the
+        emulation clock has been moved without the processor running */
+
+        self.anchor();
+        self.host_sample = self.host_anchor;
+        self.emulated_sample = self.emulated_anchor;
+    }
+
+    fn ratio(&self) -> f64 {
+        match self.speed {
+            Speed::Ratio(r) if r > 0.0 => r,
+            _ => 1.0
+        }
+    }
+
+    fn target(&self) -> EmulationTick {
+        /* Returns the emulated time that corresponds to the host time now */
+
+        self.emulated_anchor + self.host_anchor.elapsed().as_secs_f64()*self.ratio()
+    }
+
+    pub fn delay(&mut self) -> Duration {
+        /* Returns how long the host should sleep for the processor to be back
+        in step. A processor that has fallen well behind is re-anchored
+        rather than allowed to run flat out until it catches up */
+
+        if self.speed == Speed::Unthrottled {
+            self.anchor();
+            return Duration::from_secs(0);
+        }
+
+        let ahead = self.clock.read() - self.target();
+        if ahead > 0.0 {
+            Duration::from_secs_f64(ahead/self.ratio())
+        } else {
+            if -ahead > MAX_LAG*self.ratio() {
+                self.anchor();
+            }
+            Duration::from_secs(0)
+        }
+    }
+
+    pub fn idle(&mut self) {
+        /* Carries emulated time forward with host time while the processor
+        is not executing instructions */
+
+        let behind = self.target() - self.clock.read();
+        if self.speed == Speed::Unthrottled {
+            // Anchored at the end of the last time slice, so this is real time
+            self.clock.advance(self.host_anchor.elapsed().as_secs_f64());
+            self.anchor();
+        } else if behind > 0.0 {
+            self.clock.advance(behind);
+        }
+    }
+
+    pub fn achieved(&mut self) -> f64 {
+        /* Returns the ratio of emulated to host time over the most recent
+        measurement period */
+
+        let host = self.host_sample.elapsed().as_secs_f64();
+        if host >= MEASURE_PERIOD {
+            let now = self.clock.read();
+            self.achieved = (now - self.emulated_sample)/host;
+            self.host_sample = Instant::now();
+            self.emulated_sample = now;
+        }
+
+        self.achieved
+    }
+} // impl Pacer
@@ -0,0 +1,337 @@
+/***********************************************************************
+* simple-tokio/src/server/plotter.rs
+*   Module "plotter" for the 503 incremental digital plotter. Pen
+*   movements are accumulated as strokes that can be exported as SVG
+*   or PNG images.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Each command moves the pen one step in X and/or Y, or raises or
+* lowers the pen; the command bits are in the plot_code module. A step
+* is 0.01 inch. While PLOTTER MANUAL is on, the plotter is
+* offline to the processor and accepts jog commands from the operator.
+*
+* A PNG has one pixel per step, except that a drawing more than
+* MAX_PNG_SIDE steps across is scaled down by a whole factor to fit.
+* One that would need a factor above MAX_PNG_SCALE is refused; it can
+* still be exported as SVG.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Implement the Device trait.
+*   Report when the device will next be ready.
+*   Save and restore the device state in snapshots.
+*   Limit the size of PNG exports.
+*   Stop the plotter on CLEAR; a fresh sheet is now new_sheet.
+*   Move the command bits to the shared plot_code module.
+*   Take the file names sent by clients within the media directory.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use crate::plot_code::{MINUS_X, MINUS_Y, PEN_DOWN, PEN_UP, PLUS_X, PLUS_Y};
+use super::device::{CommandResult, Device};
+use super::media;
+use super::register::{EmulationClock, EmulationTick, FlipFlop};
+use super::snapshot::{Snapshot, SnapshotResult};
+
+pub const STEP_PERIOD: EmulationTick = 1.0/300.0;      // sec, 300 steps/s
+pub const PEN_PERIOD: EmulationTick = 0.1;             // sec to raise or lower the pen
+pub const MAX_PNG_SIDE: u32 = 8192;                     // pixels
+pub const MAX_PNG_SCALE: u32 = 64;                      // steps per pixel
+
+// Status word bits returned by the 77 function
+pub const STATUS_ONLINE: u64 = 1;
+pub const STATUS_PEN_DOWN: u64 = 2;
+
+type Point = (i32, i32);
+
+pub struct Plotter {
+    clock: Arc<EmulationClock>,
+    x: i32,
+    y: i32,
+    pen_down: bool,
+    strokes: Vec<Vec<Point>>,           // the last stroke is in progress while the pen is down
+    next_ready: EmulationTick,
+    pub manual: bool,
+    pub busy_ff: FlipFlop
+}
+
+impl Plotter {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        Plotter {
+            clock: clock.clone(),
+            x: 0,
+            y: 0,
+            pen_down: false,
+            strokes: Vec::new(),
+            next_ready: 0.0,
+            manual: false,
+            busy_ff: FlipFlop::new(clock)
+        }
+    }
+
+    pub fn position(&self) -> Point {
+        (self.x, self.y)
+    }
+
+    pub fn new_sheet(&mut self) {
+        /* Puts in a fresh sheet of paper with the pen at the origin */
+
+        self.strokes.clear();
+        self.x = 0;
+        self.y = 0;
+        if self.pen_down {
+            self.strokes.push(vec![(0, 0)]);
+        }
+    }
+
+    fn execute(&mut self, bits: u8) {
+        /* Applies one command and advances the time at which the plotter
+        will next be ready */
+
+        let now = self.clock.read();
+        let mut period = 0.0;
+        if bits & PEN_UP != 0 && self.pen_down {
+            self.pen_down = false;
+            period += PEN_PERIOD;
+        } else if bits & PEN_DOWN != 0 && !self.pen_down {
+            self.pen_down = true;
+            self.strokes.push(vec![(self.x, self.y)]);
+            period += PEN_PERIOD;
+        }
+
+        let dx = (bits & PLUS_X != 0) as i32 - (bits & MINUS_X != 0) as i32;
+        let dy = (bits & PLUS_Y != 0) as i32 - (bits & MINUS_Y != 0) as i32;
+        if dx != 0 || dy != 0 {
+            self.x += dx;
+            self.y += dy;
+            period += STEP_PERIOD;
+            if self.pen_down {
+                if let Some(stroke) = self.strokes.last_mut() {
+                    stroke.push((self.x, self.y));
+                }
+            }
+        }
+
+        self.next_ready = now + period;
+    }
+
+    pub fn plot(&mut self, bits: u8) -> bool {
+        /* Accepts a command from the processor, returning false if the
+        plotter is still moving or is offline */
+
+        if self.manual || self.clock.read() < self.next_ready {
+            false
+        } else {
+            self.execute(bits);
+            true
+        }
+    }
+
+    pub fn jog(&mut self, bits: u8) {
+        /* Moves the pen at the operator's request while in manual */
+
+        if self.manual {
+            self.execute(bits);
+        }
+    }
+
+    pub fn status(&self) -> u64 {
+        let mut status = 0;
+        if !self.manual {
+            status |= STATUS_ONLINE;
+        }
+
+        if self.pen_down {
+            status |= STATUS_PEN_DOWN;
+        }
+
+        status
+    }
+
+    pub fn update_lamps(&mut self) {
+        let moving = self.clock.read() < self.next_ready;
+        if moving != self.busy_ff.read() {
+            self.busy_ff.set(moving);
+        } else {
+            self.busy_ff.update_glow(0.0);
+        }
+    }
+
+    fn bounds(&self) -> (Point, Point) {
+        /* Returns the lower-left and upper-right corners of the drawing */
+
+        let mut lo = (0, 0);
+        let mut hi = (0, 0);
+        for &(x, y) in self.strokes.iter().flatten() {
+            lo = (lo.0.min(x), lo.1.min(y));
+            hi = (hi.0.max(x), hi.1.max(y));
+        }
+
+        (lo, hi)
+    }
+
+    pub fn to_svg(&self) -> String {
+        /* Renders the drawing as SVG, one user unit per step, with Y upward */
+
+        let (lo, hi) = self.bounds();
+        let width = hi.0 - lo.0 + 1;
+        let height = hi.1 - lo.1 + 1;
+        let mut svg = format!(concat!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" ",
+                "width=\"{}in\" height=\"{}in\" viewBox=\"0 0 {} {}\">\n",
+                "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n"),
+                width as f64/100.0, height as f64/100.0, width, height);
+        for stroke in self.strokes.iter() {
+            let points: Vec<String> = stroke.iter()
+                    .map(|&(x, y)| format!("{},{}", x - lo.0, hi.1 - y))
+                    .collect();
+            svg.push_str(&format!(
+                    "<polyline fill=\"none\" stroke=\"black\" stroke-width=\"1\" points=\"{}\"/>\n",
+                    points.join(" ")));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save_svg<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> image::ImageResult<()> {
+        /* Renders the drawing as a PNG, one pixel per step unless it must be
+        scaled down, with Y upward */
+
+        let (lo, hi) = self.bounds();
+        let width = (hi.0 - lo.0 + 1) as u32;
+        let height = (hi.1 - lo.1 + 1) as u32;
+        let scale = width.max(height).div_ceil(MAX_PNG_SIDE);
+        if scale > MAX_PNG_SCALE {
+            return Err(image::ImageError::Limits(image::error::LimitError::from_kind(
+                    image::error::LimitErrorKind::DimensionError)));
+        }
+
+        let mut img = image::GrayImage::from_pixel(width.div_ceil(scale),
+                height.div_ceil(scale), image::Luma([255]));
+        let mut ink = |x: i32, y: i32| img.put_pixel((x - lo.0) as u32/scale,
+                (hi.1 - y) as u32/scale, image::Luma([0]));
+        for stroke in self.strokes.iter() {
+            if stroke.len() == 1 {
+                let (x, y) = stroke[0];
+                ink(x, y);
+            }
+
+            for pair in stroke.windows(2) {
+                // Each command moves at most one step, but draw the segment in
+                // general in case strokes are ever joined
+                let (mut x, mut y) = pair[0];
+                let (x1, y1) = pair[1];
+                loop {
+                    ink(x, y);
+                    if x == x1 && y == y1 {
+                        break;
+                    }
+                    x += (x1 - x).signum();
+                    y += (y1 - y).signum();
+                }
+            }
+        }
+
+        img.save(path)
+    }
+} // impl Plotter
+
+impl Device for Plotter {
+
+    fn name(&self) -> &str {
+        "PLOTTER"
+    }
+
+    fn write_char(&mut self, ch: u8) -> bool {
+        self.plot(ch)
+    }
+
+    fn status(&self) -> u64 {
+        Plotter::status(self)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        if self.manual {None} else {Some(self.next_ready)}
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], media: &Path) -> Option<CommandResult> {
+        match code {
+            "PLJOG" => Some(deserialize(payload).map_err(Into::into)
+                    .map(|bits: u8| self.jog(bits))),
+            "PLCLR" => {
+                self.new_sheet();
+                Some(Ok(()))
+            }
+            "PLSVG" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|name: String| media::resolve(media, &name)
+                        .and_then(|path| self.save_svg(path)).map_err(Into::into))),
+            "PLPNG" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|name: String| media::resolve(media, &name).map_err(Into::into)
+                        .and_then(|path| self.save_png(path).map_err(Into::into)))),
+            _ => None
+        }
+    }
+
+    fn clear(&mut self) {
+        self.next_ready = 0.0;
+        self.busy_ff.set(false);
+    }
+
+    fn power(&mut self, _on: bool) {
+        self.manual = false;
+    }
+
+    fn update_lamps(&mut self) {
+        Plotter::update_lamps(self);
+    }
+
+    fn glow(&self) -> f32 {
+        *self.busy_ff.read_glow()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for Plotter
+
+impl Snapshot for Plotter {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(&mut *out, &(self.x, self.y, self.pen_down, &self.strokes,
+                self.next_ready, self.manual))?;
+        self.busy_ff.save(out)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (x, y, pen_down, strokes, next_ready, manual) = deserialize_from(&mut *input)?;
+        self.x = x;
+        self.y = y;
+        self.pen_down = pen_down;
+        self.strokes = strokes;
+        self.next_ready = next_ready;
+        self.manual = manual;
+        self.busy_ff.restore(input)
+    }
+} // impl Snapshot for Plotter
@@ -0,0 +1,781 @@
+/***********************************************************************
+* simple-tokio/src/server/processor.rs
+*   Module "processor" for the Elliott 503 central processor: fetches
+*   39-bit words from the store, splits them into their two 19-bit
+*   instructions, and executes the 503 order code.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Word format (bit 38 is the most significant):
+*       38-33   first function (two octal digits, group and code)
+*       32-20   first address
+*       19      B digit (modify second instruction by n of the first)
+*       18-13   second function
+*       12-0    second address
+*
+* Numbers are 39-bit two's-complement fractions. The auxiliary register
+* (AR) holds the 38 less-significant bits of a double-length value
+* whose more-significant half is in A.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Advance the emulation clock by each instruction's execution time.
+*   Save and restore the processor state in snapshots.
+*   Expose which half of the word is next, for the monitor.
+*   Record the instruction last executed, for the trace.
+*   Use the register arithmetic for shifts, multiplication and division.
+*   Treat A and AR as a register pair for double-length operations.
+*   Use the exact floating-point unit for group 6.
+*   Write the store before changing A, so a protected word changes nothing.
+*   Write the A and AR pair as "A,AR" in comments, AR alone as "AR".
+*   Stop on a block transfer to a unit with no device attached.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use bincode::{serialize_into, deserialize_from};
+
+use super::register::{Register, RegisterPair, FlipFlop, EmulationClock};
+use super::snapshot::{Snapshot, SnapshotResult};
+use super::floating;
+use super::timing;
+use super::store::{Store, StoreError};
+pub use super::store::WORD_MASK;
+
+pub const WORD_BITS: u8 = 39;
+pub const SIGN_BIT: u64 = 0o4000000000000;
+pub const AUX_BITS: u8 = 38;
+pub const AUX_MASK: u64 = 0o3777777777777;      // 38 bits
+pub const HALF_MASK: u64 = 0o1777777;           // 19 bits
+pub const B_DIGIT: u64 = 0o2000000;             // bit 19
+pub const ADDR_BITS: u8 = 13;
+pub const ADDR_MASK: u64 = 0o17777;
+pub const FUNCTION_MASK: u64 = 0o77;
+
+// Word and instruction field extraction
+
+pub fn first_half(word: u64) -> u64 {
+    (word >> 20) & HALF_MASK
+}
+
+pub fn second_half(word: u64) -> u64 {
+    word & HALF_MASK
+}
+
+pub fn is_b_modified(word: u64) -> bool {
+    word & B_DIGIT != 0
+}
+
+pub fn function(instr: u64) -> u64 {
+    (instr >> ADDR_BITS) & FUNCTION_MASK
+}
+
+pub fn address(instr: u64) -> u64 {
+    instr & ADDR_MASK
+}
+
+pub fn instruction(func: u64, addr: u64) -> u64 {
+    /* Assembles a 19-bit instruction from its function and address */
+    ((func & FUNCTION_MASK) << ADDR_BITS) | (addr & ADDR_MASK)
+}
+
+pub fn word(first: u64, b_modify: bool, second: u64) -> u64 {
+    /* Assembles a word from its two instructions and the B digit */
+    ((first & HALF_MASK) << 20) | (if b_modify {B_DIGIT} else {0}) | (second & HALF_MASK)
+}
+
+pub fn to_signed(word: u64) -> i64 {
+    /* Sign-extends a 39-bit word to i64 */
+    ((word << (64 - WORD_BITS)) as i64) >> (64 - WORD_BITS)
+}
+
+pub fn from_signed(value: i64) -> u64 {
+    /* Truncates a signed value to a 39-bit word */
+    value as u64 & WORD_MASK
+}
+
+fn fits_word(value: i128) -> bool {
+    (-(1_i128 << (WORD_BITS-1))..(1_i128 << (WORD_BITS-1))).contains(&value)
+}
+
+
+/***** Peripheral interface *****/
+
+pub trait IoSystem {
+    /* Interface between the processor's group-7 functions and the
+    peripheral units. Unit numbers are the top five bits of the
+    instruction address. A None or false result means the unit is not
+    ready, and the processor will retry the instruction */
+
+    fn read_char(&mut self, unit: u64) -> Option<u8>;
+    fn write_char(&mut self, unit: u64, ch: u8) -> bool;
+    fn control(&mut self, unit: u64, order: u64) -> bool;
+    fn status(&mut self, unit: u64) -> u64;
+    fn transfer(&mut self, output: bool, param: u64, addr: u64, store: &mut Store) -> Result<bool, StopReason>;
+}
+
+pub fn io_unit(addr: u64) -> u64 {
+    addr >> 8
+}
+
+pub fn io_data(addr: u64) -> u64 {
+    addr & 0o377
+}
+
+// Block-transfer parameter word fields (in A for functions 75 and 76)
+
+pub fn transfer_unit(param: u64) -> u64 {
+    (param >> 34) & 0o37
+}
+
+pub fn transfer_count(param: u64) -> usize {
+    ((param >> 21) & 0o17777) as usize
+}
+
+pub fn transfer_address(param: u64) -> u64 {
+    param & 0o7777777
+}
+
+
+/***** Processor *****/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    DynamicStop(u64),                   // unconditional jump to itself at address
+    InvalidAddress(u64),                // reference outside the store
+    Protected(u64),                     // write into a protected region
+    Unassigned(u64),                    // unassigned function code
+    Parity(u64),                        // backing-store parity failure at address
+    NoUnit(u64)                         // block transfer to a unit with no device
+}
+
+impl From<StoreError> for StopReason {
+    fn from(e: StoreError) -> Self {
+        match e {
+            StoreError::NonExistent(addr) => StopReason::InvalidAddress(addr),
+            StoreError::Protected(addr) => StopReason::Protected(addr)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Continue,                           // instruction completed
+    Wait,                               // peripheral not ready, instruction will be retried
+    Stop(StopReason)                    // processor has stopped
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Executed {
+    pub addr: u64,                      // word address
+    pub second: bool,                   // second instruction of the word
+    pub instr: u64,                     // instruction as executed, after any B-modification
+    pub modified: bool                  // instruction was B-modified
+}
+
+enum Sequence {
+    Next,
+    Jump(u64, bool),                    // address, second half
+    Wait,
+    Stop(StopReason)
+}
+
+pub struct Processor {
+    clock: Arc<EmulationClock>,
+    pub a_reg: Register<u64>,           // accumulator
+    pub ar_reg: Register<u64>,          // auxiliary register
+    pub scr: Register<u64>,             // sequence control register
+    pub ir: Register<u64>,              // instruction (word) register
+    pub overflow_ff: FlipFlop,
+    pub running: bool,
+    pub executed: Option<Executed>,     // instruction completed by the last step
+    second_half: bool,                  // next instruction is the second of the word in SCR
+    pending: Option<u64>                // already-fetched (and B-modified) second instruction
+}
+
+impl Processor {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        Processor {
+            clock: clock.clone(),
+            a_reg: Register::new(WORD_BITS, clock.clone()),
+            ar_reg: Register::new(AUX_BITS, clock.clone()),
+            scr: Register::new(ADDR_BITS, clock.clone()),
+            ir: Register::new(WORD_BITS, clock.clone()),
+            overflow_ff: FlipFlop::new(clock),
+            running: false,
+            executed: None,
+            second_half: false,
+            pending: None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        /* Clears the registers and sets the sequence to the first
+        instruction of location 0 */
+
+        self.a_reg.set(0);
+        self.ar_reg.set(0);
+        self.ir.set(0);
+        self.overflow_ff.set(false);
+        self.jump(0, false);
+    }
+
+    pub fn is_second_half(&self) -> bool {
+        /* True if the next instruction is the second of the word in SCR */
+        self.second_half
+    }
+
+    pub fn jump(&mut self, addr: u64, second: bool) {
+        /* Transfers control to the first or second instruction of addr */
+
+        self.scr.set(addr);
+        self.second_half = second;
+        self.pending = None;
+    }
+
+    pub fn step(&mut self, store: &mut Store, io: &mut dyn IoSystem) -> Step {
+        /* Executes the next instruction (half-word) in sequence */
+
+        self.executed = None;
+        let addr = self.scr.read();
+        let modified = self.second_half && self.pending.is_some() && is_b_modified(self.ir.read());
+        let instr = match self.pending {
+            Some(instr) if self.second_half => instr,
+            _ => {
+                match store.read(addr) {
+                    Err(e) => return self.stop(e.into()),
+                    Ok(word) => {
+                        self.ir.set(word);
+                        if self.second_half {second_half(word)} else {first_half(word)}
+                    }
+                }
+            }
+        };
+
+        let sequence = self.execute(instr, store, io);
+        if !matches!(sequence, Sequence::Wait) {
+            self.executed = Some(Executed {
+                addr,
+                second: self.second_half,
+                instr,
+                modified
+            });
+            self.clock.advance(timing::execute_time(function(instr), address(instr)));
+        }
+
+        match sequence {
+            Sequence::Wait => Step::Wait,
+            Sequence::Stop(reason) => self.stop(reason),
+            Sequence::Jump(target, second) => {
+                if target == addr && second == self.second_half && matches!(function(instr), 0o40 | 0o44) {
+                    return self.stop(StopReason::DynamicStop(addr));
+                }
+
+                self.jump(target, second);
+                Step::Continue
+            }
+            Sequence::Next => {
+                if self.second_half {
+                    self.scr.set((addr + 1) & ADDR_MASK);
+                    self.second_half = false;
+                    self.pending = None;
+                } else {
+                    let word = self.ir.read();
+                    let mut second = second_half(word);
+                    if is_b_modified(word) {
+                        self.clock.advance(timing::b_modify_time());
+                        match store.read(address(instr)) {
+                            Err(e) => return self.stop(e.into()),
+                            Ok(n) => second = (second + n) & HALF_MASK
+                        }
+                    }
+
+                    self.second_half = true;
+                    self.pending = Some(second);
+                }
+
+                Step::Continue
+            }
+        }
+    }
+
+    fn stop(&mut self, reason: StopReason) -> Step {
+        self.running = false;
+        Step::Stop(reason)
+    }
+
+    fn set_overflow(&mut self, overflow: bool) {
+        if overflow {
+            self.overflow_ff.set(true);
+        }
+    }
+
+    fn execute(&mut self, instr: u64, store: &mut Store, io: &mut dyn IoSystem) -> Sequence {
+        /* Executes one instruction, returning how the sequence should continue */
+
+        let func = function(instr);
+        let addr = address(instr);
+        let group = func >> 3;
+
+        match group {
+            0..=3 => {
+                // Arithmetic and transfer groups: all reference the store operand n
+                let n = match store.read(addr) {
+                    Ok(n) => n,
+                    Err(e) => return Sequence::Stop(e.into())
+                };
+
+                let a = self.a_reg.read();
+                let x = if group & 1 == 0 {a} else {n};
+                let (result, overflow) = Self::arithmetic(func & 7, x, a, n);
+
+                // The store is written first, so that a protected word leaves
+                // A and the overflow flip-flop as they were
+                let (stored, a_new) = match group {
+                    0 => (Ok(()), Some(result)),
+                    1 => (store.write(addr, a), Some(result)),
+                    2 => (store.write(addr, result), None),
+                    _ => (store.write(addr, result), Some(n))
+                };
+
+                if let Err(e) = stored {
+                    return Sequence::Stop(e.into());
+                }
+
+                self.set_overflow(overflow);
+                if let Some(a_new) = a_new {
+                    self.a_reg.set(a_new);
+                }
+
+                Sequence::Next
+            }
+            4 => self.jump_group(func, addr),
+            5 => self.multiply_shift_group(func, addr, store),
+            6 => self.floating_group(func, addr, store),
+            _ => self.io_group(func, addr, store, io)
+        }
+    }
+
+    fn arithmetic(code: u64, x: u64, a: u64, n: u64) -> (u64, bool) {
+        /* Computes the eight basic operations common to groups 0-3. x is A for
+        groups 0 and 2, n for groups 1 and 3. Returns the 39-bit result and
+        whether it overflowed */
+
+        let a = to_signed(a) as i128;
+        let n = to_signed(n) as i128;
+        let result = match code {
+            0 => to_signed(x) as i128,
+            1 => -(to_signed(x) as i128),
+            2 => n + 1,
+            3 => return (a as u64 & n as u64 & WORD_MASK, false),
+            4 => a + n,
+            5 => a - n,
+            6 => 0,
+            _ => n - a
+        };
+
+        (result as u64 & WORD_MASK, !fits_word(result))
+    }
+
+    fn jump_group(&mut self, func: u64, addr: u64) -> Sequence {
+        /* Group 4: unconditional and conditional jumps. Codes 0-3 jump to
+        the first instruction of addr, codes 4-7 to the second */
+
+        let taken = match func & 3 {
+            0 => true,
+            1 => self.a_reg.read() & SIGN_BIT != 0,
+            2 => self.a_reg.read() == 0,
+            _ => {
+                let overflow = self.overflow_ff.read();
+                self.overflow_ff.set(false);
+                overflow
+            }
+        };
+
+        if taken {
+            Sequence::Jump(addr, func & 4 != 0)
+        } else {
+            Sequence::Next
+        }
+    }
+
+    fn accumulator(&mut self) -> RegisterPair<'_> {
+        /* A and AR as one double-length register */
+        RegisterPair::new(&mut self.a_reg, &mut self.ar_reg)
+    }
+
+    fn multiply_shift_group(&mut self, func: u64, addr: u64, store: &mut Store) -> Sequence {
+        /* Group 5: shifts, multiplication and division. Shift counts are
+        taken from the low-order seven bits of the address */
+
+        let count = (addr & 0o177) as u32;
+        match func {
+            0o50 => {                   // A,AR' = A,AR * 2^-N
+                self.accumulator().shift_right(count);
+            }
+            0o51 => {                   // A' = A * 2^-N, logical; AR' = 0
+                self.a_reg.shift_right_logical(count);
+                self.ar_reg.set(0);
+            }
+            0o54 => {                   // A,AR' = A,AR * 2^N
+                self.accumulator().shift_left(count);
+            }
+            0o55 => {                   // A' = A * 2^N; AR' = 0
+                self.a_reg.shift_left(count);
+                self.ar_reg.set(0);
+            }
+            0o57 => {                   // A' = AR
+                self.a_reg.set(self.ar_reg.read());
+            }
+            _ => {
+                let n = match store.read(addr) {
+                    Ok(n) => n,
+                    Err(e) => return Sequence::Stop(e.into())
+                };
+
+                match func {
+                    0o52 => {           // A,AR' = A * n (double length)
+                        self.accumulator().multiply(n);
+                    }
+                    0o53 => {           // A' = A * n (rounded); AR' = 0
+                        self.a_reg.multiply_rounded(n);
+                        self.ar_reg.set(0);
+                    }
+                    _ => {              // A' = A,AR / n; AR' = 0
+                        self.accumulator().divide(n);
+                    }
+                }
+            }
+        }
+
+        let overflow = self.a_reg.take_overflow();
+        self.set_overflow(overflow);
+        Sequence::Next
+    }
+
+    fn floating_group(&mut self, func: u64, addr: u64, store: &mut Store) -> Sequence {
+        /* Group 6: floating-point arithmetic (see floating.rs). A floating
+        overflow leaves A unchanged and sets the overflow flip-flop */
+
+        let a = self.a_reg.read();
+        let result = match func {
+            0o65 => floating::standardize(a),   // A' = A standardized (fixed to floating)
+            0o66 | 0o67 => return Sequence::Stop(StopReason::Unassigned(func)),
+            _ => {
+                let n = match store.read(addr) {
+                    Ok(n) => n,
+                    Err(e) => return Sequence::Stop(e.into())
+                };
+
+                match func {
+                    0o60 => floating::add(a, n),
+                    0o61 => floating::subtract(a, n),
+                    0o62 => floating::subtract(n, a),
+                    0o63 => floating::multiply(a, n),
+                    _ => floating::divide(a, n)
+                }
+            }
+        };
+
+        match result {
+            Some(word) => self.a_reg.set(word),
+            None => self.set_overflow(true)
+        }
+
+        Sequence::Next
+    }
+
+    fn io_group(&mut self, func: u64, addr: u64, store: &mut Store, io: &mut dyn IoSystem) -> Sequence {
+        /* Group 7: input/output and the subroutine link */
+
+        match func {
+            0o70 => {                   // A' = word generator (none fitted to this panel)
+                self.a_reg.set(0);
+            }
+            0o71 => {                   // A' = A | input character
+                match io.read_char(io_unit(addr)) {
+                    None => return Sequence::Wait,
+                    Some(ch) => self.a_reg.set(self.a_reg.read() | ch as u64)
+                }
+            }
+            0o72 => {                   // control order to unit
+                if !io.control(io_unit(addr), io_data(addr)) {
+                    return Sequence::Wait;
+                }
+            }
+            0o73 => {                   // n' = SCR
+                if let Err(e) = store.write(addr, self.scr.read()) {
+                    return Sequence::Stop(e.into());
+                }
+            }
+            0o74 => {                   // output character to unit
+                if !io.write_char(io_unit(addr), io_data(addr) as u8) {
+                    return Sequence::Wait;
+                }
+            }
+            0o75 | 0o76 => {            // block transfer in (75) or out (76) at addr
+                match io.transfer(func == 0o76, self.a_reg.read(), addr, store) {
+                    Ok(true) => {}
+                    Ok(false) => return Sequence::Wait,
+                    Err(reason) => return Sequence::Stop(reason)
+                }
+            }
+            0o77 => {                   // A' = unit status
+                self.a_reg.set(io.status(io_unit(addr)) & WORD_MASK);
+            }
+            _ => return Sequence::Stop(StopReason::Unassigned(func))
+        }
+
+        Sequence::Next
+    }
+} // impl Processor
+
+impl Snapshot for Processor {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        self.a_reg.save(out)?;
+        self.ar_reg.save(out)?;
+        self.scr.save(out)?;
+        self.ir.save(out)?;
+        self.overflow_ff.save(out)?;
+        serialize_into(out, &(self.running, self.second_half, self.pending))
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        self.a_reg.restore(input)?;
+        self.ar_reg.restore(input)?;
+        self.scr.restore(input)?;
+        self.ir.restore(input)?;
+        self.overflow_ff.restore(input)?;
+        let (running, second_half, pending) = deserialize_from(input)?;
+        self.running = running;
+        self.second_half = second_half;
+        self.pending = pending;
+        Ok(())
+    }
+} // impl Snapshot for Processor
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF: u64 = 0o2000000000000;          // 0.5
+    const QUARTER: u64 = 0o1000000000000;       // 0.25
+
+    struct TestIo {
+        tape: Vec<u8>                           // characters to be read, from unit 0
+    }
+
+    impl IoSystem for TestIo {
+        fn read_char(&mut self, _unit: u64) -> Option<u8> {
+            if self.tape.is_empty() {None} else {Some(self.tape.remove(0))}
+        }
+
+        fn write_char(&mut self, _unit: u64, _ch: u8) -> bool {
+            true
+        }
+
+        fn control(&mut self, _unit: u64, _order: u64) -> bool {
+            true
+        }
+
+        fn status(&mut self, unit: u64) -> u64 {
+            unit + 0o10
+        }
+
+        fn transfer(&mut self, _output: bool, _param: u64, _addr: u64, _store: &mut Store) -> Result<bool, StopReason> {
+            Ok(true)
+        }
+    }
+
+    struct Machine {
+        processor: Processor,
+        store: Store,
+        io: TestIo
+    }
+
+    impl Machine {
+        fn new(words: &[(u64, u64)]) -> Self {
+            /* Builds a machine with words placed at their addresses, ready to
+            start at the first instruction of location 0 */
+
+            let clock = Arc::new(EmulationClock::new(0.0));
+            let mut store = Store::new(1, clock.clone());
+            for &(addr, w) in words {
+                store.poke(addr, w).unwrap();
+            }
+
+            let mut processor = Processor::new(clock);
+            processor.clear();
+            processor.running = true;
+            Machine {processor, store, io: TestIo {tape: Vec::new()}}
+        }
+
+        fn step(&mut self) -> Step {
+            self.processor.step(&mut self.store, &mut self.io)
+        }
+
+        fn run(&mut self, steps: usize) {
+            for _ in 0..steps {
+                assert_eq!(self.step(), Step::Continue);
+            }
+        }
+
+        fn a(&self) -> u64 {
+            self.processor.a_reg.read()
+        }
+
+        fn n(&self, addr: u64) -> u64 {
+            self.store.peek(addr).unwrap()
+        }
+    }
+
+    fn pair(first: (u64, u64), second: (u64, u64)) -> u64 {
+        word(instruction(first.0, first.1), false, instruction(second.0, second.1))
+    }
+
+    #[test]
+    fn group_0_to_3() {
+        // 00-07 change A only, 10-17 also put A in n, 20-27 put the result
+        // in n only, 30-37 put it in n and the old n in A
+        let mut m = Machine::new(&[
+            (0, pair((0o04, 100), (0o10, 101))),
+            (1, pair((0o24, 102), (0o32, 103))),
+            (2, pair((0o07, 104), (0o06, 0))),
+            (100, 3), (101, 7), (102, 10), (103, 20), (104, 5)]);
+        m.processor.a_reg.set(2);
+        m.run(2);
+        assert_eq!((m.a(), m.n(101)), (7, 5));              // A' = A+n = 5; then exchange
+        m.run(2);
+        assert_eq!((m.a(), m.n(102)), (20, 17));            // n' = A+n; then A' = n, n' = n+1
+        assert_eq!(m.n(103), 21);
+        m.step();
+        assert_eq!(m.a(), from_signed(5 - 20));             // A' = n-A
+        m.step();
+        assert_eq!(m.a(), 0);
+        assert!(!m.processor.overflow_ff.read());
+    }
+
+    #[test]
+    fn overflow_test_and_clear() {
+        // 0.5 + 0.5 overflows; 47 jumps on it and clears it, so the 43
+        // that follows falls through
+        let mut m = Machine::new(&[
+            (0, pair((0o04, 100), (0o47, 3))),
+            (3, pair((0o40, 0), (0o43, 6))),
+            (100, HALF)]);
+        m.processor.a_reg.set(HALF);
+        m.step();
+        assert!(m.processor.overflow_ff.read());
+        m.step();
+        assert_eq!((m.processor.scr.read(), m.processor.is_second_half()), (3, true));
+        assert!(!m.processor.overflow_ff.read());
+        m.step();
+        assert_eq!((m.processor.scr.read(), m.processor.is_second_half()), (4, false));
+    }
+
+    #[test]
+    fn group_4_jumps() {
+        // 41 on a negative A, 42 on a zero A, not taken otherwise
+        let mut m = Machine::new(&[
+            (0, pair((0o41, 10), (0o42, 20))),
+            (1, pair((0o01, 0), (0o41, 30))),
+            (30, pair((0o06, 0), (0o46, 40)))]);
+        m.processor.a_reg.set(1);
+        m.run(3);
+        assert_eq!(m.processor.scr.read(), 1);              // 41 and 42 fell through; A' = -A
+        m.step();
+        assert_eq!((m.processor.scr.read(), m.processor.is_second_half()), (30, false));
+        m.run(2);
+        assert_eq!((m.processor.scr.read(), m.processor.is_second_half()), (40, true));
+    }
+
+    #[test]
+    fn group_5_shifts_and_multiply() {
+        let mut m = Machine::new(&[
+            (0, pair((0o55, 3), (0o51, 1))),
+            (1, pair((0o30, 100), (0o53, 100))),
+            (100, HALF)]);
+        m.processor.a_reg.set(1);
+        m.step();
+        assert_eq!(m.a(), 8);
+        m.step();
+        assert_eq!(m.a(), 4);
+        m.run(2);                                           // A' = 0.5; A' = 0.5*0.5
+        assert_eq!(m.a(), QUARTER);
+        assert_eq!(m.processor.ar_reg.read(), 0);
+    }
+
+    #[test]
+    fn group_6_floating() {
+        // Standardize 0.5 and add it to itself
+        let mut m = Machine::new(&[
+            (0, pair((0o65, 0), (0o60, 100))),
+            (1, pair((0o66, 0), (0o40, 0)))]);
+        m.store.poke(100, floating::standardize(HALF).unwrap()).unwrap();
+        m.processor.a_reg.set(HALF);
+        m.run(2);
+        assert_eq!(floating::value(m.a()), 1.0);
+        assert_eq!(m.step(), Step::Stop(StopReason::Unassigned(0o66)));
+    }
+
+    #[test]
+    fn group_7_io() {
+        // Read a character, wait for the next, store SCR, read status
+        let mut m = Machine::new(&[
+            (0, pair((0o71, 0), (0o71, 0))),
+            (1, pair((0o73, 100), (0o77, 2 << 8)))]);
+        m.io.tape = vec![0o21];
+        m.processor.a_reg.set(0o100);
+        m.step();
+        assert_eq!(m.a(), 0o121);
+        assert_eq!(m.step(), Step::Wait);
+        assert_eq!(m.processor.executed, None);
+        m.io.tape = vec![0o02];
+        m.step();
+        assert_eq!(m.a(), 0o123);
+        m.run(2);
+        assert_eq!(m.n(100), 1);
+        assert_eq!(m.a(), 0o12);
+    }
+
+    #[test]
+    fn b_modification() {
+        // The second instruction is modified by n of the first, in its
+        // address and, past the address bits, in its function
+        let mut m = Machine::new(&[
+            (0, word(instruction(0o00, 100), true, instruction(0o04, 0))),
+            (1, word(instruction(0o00, 101), true, instruction(0o04, 0))),
+            (5, 7), (100, 5), (101, instruction(0o01, 102)), (102, 3)]);
+        m.run(2);
+        assert_eq!(m.a(), 7);                               // 04 0 became 04 5
+        assert_eq!(m.processor.executed, Some(Executed {
+                addr: 0, second: true, instr: instruction(0o04, 5), modified: true}));
+        m.run(2);
+        assert_eq!(m.a(), from_signed(7 - 3));              // 04 0 became 05 102
+        assert_eq!(m.n(101), instruction(0o01, 102));       // the store is not changed
+    }
+
+    #[test]
+    fn dynamic_stop() {
+        // A 40 or 44 to itself stops; a jump to the other half does not
+        let mut m = Machine::new(&[
+            (0, pair((0o44, 0), (0o40, 1))),
+            (1, pair((0o00, 0), (0o44, 1))),
+            (2, pair((0o40, 2), (0o00, 0)))]);
+        m.run(3);
+        assert_eq!(m.step(), Step::Stop(StopReason::DynamicStop(1)));
+        assert!(!m.processor.running);
+
+        m.processor.jump(2, false);
+        m.processor.running = true;
+        assert_eq!(m.step(), Step::Stop(StopReason::DynamicStop(2)));
+    }
+}
@@ -6,16 +6,37 @@
 * Licensed under the MIT License, see
 *       http://www.opensource.org/licenses/mit-license.php
 ************************************************************************
+* Register<u64> also provides the 503's arithmetic on words of up to 64
+* bits. Values are two's-complement fractions: the sign digit has weight
+* -1 and the others 2^-1, 2^-2, ... A double-length value is held in a
+* RegisterPair: a register and an auxiliary register one digit shorter,
+* which extends the fraction with less-significant digits and has no
+* sign of its own.
+* Operations whose result does not fit set the register's overflow
+* flag, which stays set until read by take_overflow or cleared.
+************************************************************************
 * Modification log.
 * 2021-01-24  P.Kimpel
 *   Original version, from simple-system/src/server/register.rs.
+* 2026-10-16  agent
+*   Save and restore register and flip-flop values in snapshots.
+*   Add 503 fraction arithmetic, shifts, multiply and divide for
+*   Register<u64>, and make the overflow flag readable.
+*   Move the double-length operations to the new RegisterPair.
+*   Detect lost digits before a long double-length left shift.
+*   Round double-length quotients down for negative divisors too.
 ***********************************************************************/
 
 #![allow(unused_variables, dead_code)]     // for now...
 
+use std::io::{Read, Write};
 use std::ops::*;
 use std::sync::{Arc, Mutex};
 
+use bincode::{serialize_into, deserialize_from};
+
+use super::snapshot::{Snapshot, SnapshotResult};
+
 pub type EmulationTick = f64;
 
 pub const CLOCK_PERIOD: EmulationTick = 0.30e-6;
@@ -45,6 +66,10 @@ impl EmulationClock {
     pub fn read(&self) -> EmulationTick {
         *self.ticks.lock().unwrap()
     }
+
+    pub fn set(&self, ticks: EmulationTick) {
+        *self.ticks.lock().unwrap() = ticks;
+    }
 } // impl Emulation Clock
 
 
@@ -116,13 +141,30 @@ where T: Copy + Eq +
         self.update_glow(0.0);
     }
 
+    pub fn overflow(&self) -> bool {
+        self.overflow
+    }
+
+    pub fn clear_overflow(&mut self) {
+        self.overflow = false;
+    }
+
+    pub fn take_overflow(&mut self) -> bool {
+        /* Returns the overflow flag and clears it, as the 503's
+        test-overflow jumps do */
+
+        let overflow = self.overflow;
+        self.overflow = false;
+        overflow
+    }
+
     pub fn add(&mut self, value: T) {
         let augend = self.value;
+        let value = value & self.mask;
         let result = augend + value;
-        if (augend & self.sign_mask) == (value & self.sign_mask) {
-            if (value & self.sign_mask) != (result & self.sign_mask) {
-                self.overflow = true;
-            }
+        if (augend & self.sign_mask) == (value & self.sign_mask) &&
+                (value & self.sign_mask) != (result & self.sign_mask) {
+            self.overflow = true;
         }
 
         self.value = result & self.mask;
@@ -131,8 +173,8 @@ where T: Copy + Eq +
 
     pub fn add_unsigned(&mut self, value: T) {
         let augend = self.value;
-        let result = augend + value;
-        if (value & self.power_mask) != (result & self.power_mask) {
+        let result = augend + (value & self.mask);
+        if (result & self.power_mask) != T::from(0) {
             self.overflow = true;
         }
 
@@ -141,11 +183,252 @@ where T: Copy + Eq +
     }
 
     pub fn negate(&mut self) {
-        self.value = self.power_mask - self.value;
+        /* Forms the two's complement; only the most negative value
+        overflows */
+
+        if self.value == self.sign_mask {
+            self.overflow = true;
+        }
+
+        self.value = (self.power_mask - self.value) & self.mask;
         self.update_glow(0.0);
     }
 } // impl Register
 
+impl Register<u64> {
+
+    fn extend(&self, value: u64) -> i128 {
+        /* Sign-extends a value of this register's width */
+
+        let unused = 64 - self.bits as u32;
+        (((value << unused) as i64) >> unused) as i128
+    }
+
+    fn fits(&self, value: i128) -> bool {
+        let limit = 1_i128 << (self.bits - 1);
+        (-limit..limit).contains(&value)
+    }
+
+    pub fn signed(&self) -> i64 {
+        /* Returns the value as a signed integer */
+        self.extend(self.value) as i64
+    }
+
+    pub fn fraction(&self) -> f64 {
+        /* Returns the value as a fraction, -1 <= f < 1 */
+        self.signed() as f64 / (1_u64 << (self.bits - 1)) as f64
+    }
+
+    pub fn set_signed(&mut self, value: i128) {
+        /* Sets the register from a signed integer, truncating it and
+        setting overflow if it does not fit */
+
+        if !self.fits(value) {
+            self.overflow = true;
+        }
+
+        self.set(value as u64);
+    }
+
+    pub fn subtract(&mut self, value: u64) {
+        let difference = self.extend(self.value) - self.extend(value & self.mask);
+        self.set_signed(difference);
+    }
+
+    pub fn shift_left(&mut self, places: u32) {
+        /* Multiplies by 2^places, setting overflow if any significant
+        digit is lost */
+
+        let value = self.extend(self.value);
+        let limit = 2*self.bits as u32;
+        if places >= limit {
+            if value != 0 {
+                self.overflow = true;
+            }
+            self.set(0);
+        } else {
+            self.set_signed(value << places);
+        }
+    }
+
+    pub fn shift_right(&mut self, places: u32) {
+        /* Multiplies by 2^-places, propagating the sign and truncating
+        toward minus infinity */
+
+        let value = self.extend(self.value);
+        self.set_signed(value >> places.min(self.bits as u32));
+    }
+
+    pub fn shift_right_logical(&mut self, places: u32) {
+        /* Shifts right, filling with zeros */
+
+        let value = if places >= self.bits as u32 {0} else {self.value >> places};
+        self.set(value);
+    }
+
+    pub fn multiply_rounded(&mut self, multiplier: u64) {
+        /* Replaces this register with its product with multiplier, rounded
+        to single length */
+
+        let product = self.extend(self.value) * self.extend(multiplier & self.mask);
+        let fraction = self.bits as u32 - 1;
+        self.set_signed((product + (1_i128 << (fraction - 1))) >> fraction);
+    }
+} // impl Register<u64>
+
+
+/***** Register pair *****/
+
+pub struct RegisterPair<'a> {
+    /* A double-length register formed from a register and an auxiliary
+    register one digit shorter that extends it, as the 503's A and AR
+    form a 77-digit register for multiplication, division and the
+    double-length shifts. The registers keep their own lamp glow; the
+    pair only borrows them. Overflow is flagged in the high register */
+
+    pub high: &'a mut Register<u64>,
+    pub low: &'a mut Register<u64>
+}
+
+impl<'a> RegisterPair<'a> {
+
+    pub fn new(high: &'a mut Register<u64>, low: &'a mut Register<u64>) -> Self {
+        RegisterPair {high, low}
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.high.bits + self.low.bits
+    }
+
+    fn fits(&self, value: i128) -> bool {
+        let limit = 1_i128 << (self.bits() - 1);
+        (-limit..limit).contains(&value)
+    }
+
+    pub fn read(&self) -> i128 {
+        /* Returns the double-length value as a signed integer */
+        (self.high.extend(self.high.value) << self.low.bits) | self.low.value as i128
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.read() as f64 / 2_f64.powi(self.bits() as i32 - 1)
+    }
+
+    pub fn set(&mut self, value: i128) {
+        /* Sets both registers from a double-length value, truncating it and
+        setting overflow if it does not fit */
+
+        if !self.fits(value) {
+            self.high.overflow = true;
+        }
+
+        self.high.set((value >> self.low.bits) as u64);
+        self.low.set(value as u64);
+    }
+
+    pub fn overflow(&self) -> bool {
+        self.high.overflow()
+    }
+
+    pub fn take_overflow(&mut self) -> bool {
+        self.high.take_overflow()
+    }
+
+    pub fn shift_left(&mut self, places: u32) {
+        /* Multiplies the double-length value by 2^places, setting overflow
+        if any significant digit is lost */
+
+        // The value is checked before it is shifted, since a long shift of
+        // a double-length value can carry digits out of an i128 as well
+        let value = self.read();
+        let bits = self.bits() as u32;
+        let places = places.min(bits);
+        let bound = (1_i128 << (bits - 1)) >> places;
+        if value != 0 && !(-bound..bound).contains(&value) {
+            self.high.overflow = true;
+        }
+
+        if places >= bits {
+            self.set(0);
+        } else {
+            self.set(value << places);
+        }
+    }
+
+    pub fn shift_right(&mut self, places: u32) {
+        /* Multiplies the double-length value by 2^-places, propagating the
+        sign */
+
+        let value = self.read();
+        self.set(value >> places.min(self.bits() as u32));
+    }
+
+    pub fn multiply(&mut self, multiplier: u64) {
+        /* Replaces the pair with the full product of the high register and
+        multiplier. Only -1 * -1 overflows */
+
+        let product = self.high.extend(self.high.value) * self.high.extend(multiplier & self.high.mask);
+        self.set(product);
+    }
+
+    pub fn quotient_remainder(&self, divisor: u64) -> Option<(i128, i128)> {
+        /* Divides the double-length dividend by divisor, returning the
+        quotient, rounded down (towards minus infinity) whatever the signs,
+        and the remainder, which takes the sign of the divisor. Both are
+        scaled as the dividend's less-significant digits. Returns None for
+        division by zero */
+
+        let divisor = self.high.extend(divisor & self.high.mask);
+        if divisor == 0 {
+            None
+        } else {
+            let dividend = self.read();
+            let mut quotient = dividend / divisor;
+            if dividend % divisor != 0 && (dividend < 0) != (divisor < 0) {
+                quotient -= 1;
+            }
+
+            Some((quotient, dividend - quotient*divisor))
+        }
+    }
+
+    pub fn divide(&mut self, divisor: u64) -> i128 {
+        /* Places the quotient of the double-length dividend by divisor in
+        the high register and clears the low one, returning the remainder.
+        A quotient that does not fit sets overflow; division by zero sets
+        overflow, leaves the high register as it was, and returns zero */
+
+        let remainder = match self.quotient_remainder(divisor) {
+            Some((quotient, remainder)) => {
+                self.high.set_signed(quotient);
+                remainder
+            }
+            None => {
+                self.high.overflow = true;
+                0
+            }
+        };
+
+        self.low.set(0);
+        remainder
+    }
+} // impl RegisterPair
+
+impl Snapshot for Register<u64> {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(out, &(self.value, self.overflow))
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (value, overflow): (u64, bool) = deserialize_from(input)?;
+        self.value = value & self.mask;
+        self.overflow = overflow;
+        self.last_tick = self.clock.read();
+        Ok(())
+    }
+} // impl Snapshot for Register
+
 pub struct FlipFlop {
     clock: Arc<EmulationClock>,
     last_tick: EmulationTick,
@@ -191,4 +474,232 @@ impl FlipFlop {
         self.value = value;
         self.update_glow(0.0);
     }
-} // impl FlipFlop
\ No newline at end of file
+} // impl FlipFlop
+
+impl Snapshot for FlipFlop {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(out, &self.value)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        self.value = deserialize_from(input)?;
+        self.last_tick = self.clock.read();
+        Ok(())
+    }
+} // impl Snapshot for FlipFlop
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF: u64 = 0o2000000000000;          // 0.5
+    const QUARTER: u64 = 0o1000000000000;       // 0.25
+    const MINUS_ONE: u64 = 0o4000000000000;     // -1.0
+    const MINUS_HALF: u64 = 0o6000000000000;    // -0.5
+    const MINUS_QUARTER: u64 = 0o7000000000000; // -0.25
+
+    fn registers() -> (Register<u64>, Register<u64>) {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        (Register::new(39, clock.clone()), Register::new(38, clock))
+    }
+
+    fn word(value: u64) -> Register<u64> {
+        let (mut a, _) = registers();
+        a.set(value);
+        a
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(word(HALF).fraction(), 0.5);
+        assert_eq!(word(MINUS_ONE).fraction(), -1.0);
+        assert_eq!(word(MINUS_QUARTER).fraction(), -0.25);
+        assert_eq!(word(0o7777777777777).signed(), -1);
+        assert_eq!(word(0o3777777777777).signed(), (1 << 38) - 1);
+    }
+
+    #[test]
+    fn add_and_subtract() {
+        let mut a = word(QUARTER);
+        a.add(QUARTER);
+        assert_eq!((a.read(), a.overflow()), (HALF, false));
+        a.add(HALF);
+        assert_eq!((a.read(), a.overflow()), (MINUS_ONE, true));
+        assert!(a.take_overflow());
+        assert!(!a.overflow());
+
+        let mut a = word(MINUS_HALF);
+        a.subtract(HALF);
+        assert_eq!((a.read(), a.overflow()), (MINUS_ONE, false));
+        a.subtract(QUARTER);
+        assert!(a.overflow());
+        a.clear_overflow();
+        assert!(!a.overflow());
+    }
+
+    #[test]
+    fn negate() {
+        let mut a = word(QUARTER);
+        a.negate();
+        assert_eq!((a.read(), a.overflow()), (MINUS_QUARTER, false));
+        let mut a = word(0);
+        a.negate();
+        assert_eq!((a.read(), a.overflow()), (0, false));
+        let mut a = word(MINUS_ONE);
+        a.negate();
+        assert_eq!((a.read(), a.overflow()), (MINUS_ONE, true));
+    }
+
+    #[test]
+    fn single_shifts() {
+        let mut a = word(QUARTER);
+        a.shift_left(1);
+        assert_eq!((a.read(), a.overflow()), (HALF, false));
+        a.shift_left(1);
+        assert!(a.overflow());
+
+        let mut a = word(MINUS_QUARTER);
+        a.shift_left(2);
+        assert_eq!((a.read(), a.overflow()), (MINUS_ONE, false));
+        a.shift_left(100);
+        assert_eq!((a.read(), a.overflow()), (0, true));
+
+        let mut a = word(MINUS_HALF);
+        a.shift_right(1);
+        assert_eq!(a.read(), MINUS_QUARTER);
+        a.shift_right(50);
+        assert_eq!(a.signed(), -1);
+
+        let mut a = word(MINUS_ONE);
+        a.shift_right_logical(1);
+        assert_eq!(a.read(), HALF);
+        a.shift_right_logical(39);
+        assert_eq!(a.read(), 0);
+    }
+
+    #[test]
+    fn double_shifts() {
+        let (mut a, mut ar) = registers();
+        let mut pair = RegisterPair::new(&mut a, &mut ar);
+        pair.low.set(1);
+        pair.shift_left(38);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.overflow()), (1, 0, false));
+        pair.shift_right(1);
+        assert_eq!((pair.high.read(), pair.low.read()), (0, 0o2000000000000));
+
+        pair.high.set(MINUS_HALF);
+        pair.low.set(0);
+        pair.shift_right(39);
+        assert_eq!((pair.high.signed(), pair.low.read()), (-1, 0o3000000000000));
+        assert_eq!(pair.fraction(), -0.5/2_f64.powi(39));
+        pair.shift_left(39);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.overflow()), (MINUS_HALF, 0, false));
+        pair.shift_left(2);
+        assert!(pair.take_overflow());
+        pair.high.set(HALF);
+        pair.shift_left(200);
+        assert_eq!((pair.read(), pair.overflow()), (0, true));
+    }
+
+    #[test]
+    fn long_double_shifts() {
+        let (mut a, mut ar) = registers();
+        let mut pair = RegisterPair::new(&mut a, &mut ar);
+        pair.high.set(HALF);
+        pair.shift_left(53);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.take_overflow()), (0, 0, true));
+
+        pair.set(1);
+        pair.shift_left(75);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.overflow()), (HALF, 0, false));
+        pair.set(1);
+        pair.shift_left(76);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.take_overflow()), (MINUS_ONE, 0, true));
+
+        pair.set(-1);
+        pair.shift_left(76);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.overflow()), (MINUS_ONE, 0, false));
+        pair.shift_left(1);
+        assert_eq!((pair.read(), pair.take_overflow()), (0, true));
+        pair.set(-1);
+        pair.shift_left(77);
+        assert_eq!((pair.read(), pair.take_overflow()), (0, true));
+
+        pair.high.set(MINUS_HALF);
+        pair.low.set(0);
+        pair.shift_left(53);
+        assert!(pair.take_overflow());
+        pair.set(-3);
+        pair.shift_left(127);
+        assert_eq!((pair.read(), pair.take_overflow()), (0, true));
+    }
+
+    #[test]
+    fn multiply() {
+        let (mut a, mut ar) = registers();
+        let mut pair = RegisterPair::new(&mut a, &mut ar);
+        pair.high.set(HALF);
+        pair.multiply(MINUS_HALF);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.overflow()), (MINUS_QUARTER, 0, false));
+
+        pair.high.set(1);
+        pair.low.set(0);
+        pair.multiply(1);                   // 2^-38 * 2^-38 = 2^-76
+        assert_eq!((pair.high.read(), pair.low.read()), (0, 1));
+
+        pair.high.set(0o3777777777777);
+        pair.multiply(0o3777777777777);     // (1 - 2^-38)^2 = 1 - 2^-37 + 2^-76
+        assert_eq!((pair.high.read(), pair.low.read()), (0o3777777777776, 1));
+
+        pair.high.set(MINUS_ONE);
+        pair.multiply(MINUS_ONE);
+        assert!(pair.overflow());
+
+        let mut a = word(HALF);
+        a.multiply_rounded(HALF);
+        assert_eq!((a.read(), a.overflow()), (QUARTER, false));
+        let mut a = word(1);
+        a.multiply_rounded(HALF);           // 2^-39 rounds up to 2^-38
+        assert_eq!(a.read(), 1);
+    }
+
+    #[test]
+    fn divide() {
+        let (mut a, mut ar) = registers();
+        let mut pair = RegisterPair::new(&mut a, &mut ar);
+        pair.high.set(QUARTER);
+        pair.low.set(0o1234);
+        assert_eq!(pair.divide(HALF), 0o1234);
+        assert_eq!((pair.high.read(), pair.low.read(), pair.overflow()), (HALF, 0, false));
+
+        pair.high.set(MINUS_QUARTER);
+        assert_eq!(pair.divide(HALF), 0);
+        assert_eq!((pair.high.read(), pair.overflow()), (MINUS_HALF, false));
+
+        pair.high.set(0);
+        pair.low.set(7);
+        assert_eq!(pair.quotient_remainder(1 << 39), None);
+        assert_eq!(pair.quotient_remainder(3), Some((2, 1)));
+        let minus_three = 0o7777777777775;
+        assert_eq!(pair.quotient_remainder(minus_three), Some((-3, -2)));   // rounded down
+        pair.high.set(0o7777777777777);
+        pair.low.set((1 << 38) - 7);        // -7 in the least-significant digits
+        assert_eq!(pair.quotient_remainder(minus_three), Some((2, -1)));
+        assert_eq!(pair.quotient_remainder(3), Some((-3, 2)));
+        pair.high.set(0);
+        pair.low.set(7);
+        pair.low.set(7);
+        pair.high.set(MINUS_ONE);           // -1 + 7*2^-76
+        assert_eq!(pair.quotient_remainder(HALF).map(|(q, _)| q), Some(-(1 << 39)));
+
+        pair.high.set(HALF);
+        pair.divide(QUARTER);
+        assert!(pair.take_overflow());
+
+        pair.high.set(QUARTER);
+        assert_eq!(pair.divide(0), 0);
+        assert_eq!((pair.high.read(), pair.overflow()), (QUARTER, true));
+    }
+}
@@ -0,0 +1,238 @@
+/***********************************************************************
+* simple-tokio/src/server/snapshot.rs
+*   Module "snapshot" saves the complete state of the emulated 503 to a
+*   host file and restores it, so a long run can be checkpointed or a
+*   machine state passed to someone else.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* A snapshot file begins with the eight bytes "E503SNAP" and a version
+* number, followed by the bincode encoding of each component in a fixed
+* order: the emulation clock and console switches, the processor, the
+* store, and then each device on the I/O bus in the order attached.
+* Each component writes and reads its own fields through the Snapshot
+* trait. Lamp glow is not saved; it settles within a few frames. A
+* snapshot can also be read only as far as the store, for looking at
+* the program it holds, which leaves the devices and their host files
+* alone.
+*
+* Tape images, the backing store and the printed output held by the
+* devices are saved in full. Output devices that write to host files
+* save how much they had written; when restored, they reopen the files
+* and cut off anything written after the snapshot was taken, so output
+* is not repeated when a run is taken up again from a checkpoint.
+*
+* A restore reads the whole snapshot into a newly built machine, which
+* replaces the running one only once every component has been read, so
+* a snapshot that is damaged or does not fit leaves the machine as it
+* was, apart from an output file already cut back by a device read
+* before the one that failed. The monitor's breakpoints and any trace in
+* progress carry over.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Cut output files back to their length at the snapshot on restore.
+*   Add restore_store, which reads no further than the store.
+*   Restore into a new machine, replacing the old only if all is read.
+*   Version 3: the magnetic tape controller saves its handler count.
+*   Keep counting teleprinter restores across the change of machine.
+*   Keep the server's media and snapshot directories across a restore.
+***********************************************************************/
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use bincode::{serialize_into, deserialize_from};
+
+use super::{ServerState, new_state};
+use super::typewriter::Typewriter;
+
+pub const MAGIC: [u8; 8] = *b"E503SNAP";
+pub const VERSION: u32 = 3;
+
+pub type SnapshotResult = bincode::Result<()>;
+
+pub trait Snapshot {
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult;
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult;
+}
+
+pub fn mismatch(message: String) -> bincode::Error {
+    /* Builds the error for a snapshot that does not fit this machine */
+    Box::new(bincode::ErrorKind::Custom(message))
+}
+
+pub fn written(file: &BufWriter<File>) -> io::Result<u64> {
+    /* Returns the length of an output file, including what is still
+    buffered */
+
+    Ok(file.get_ref().metadata()?.len() + file.buffer().len() as u64)
+}
+
+pub fn reopen<P: AsRef<Path>>(path: P, length: u64) -> io::Result<File> {
+    /* Opens an output file to carry on writing after its first length
+    bytes, discarding the rest */
+
+    let mut file = OpenOptions::new().write(true).create(true).truncate(false).open(path)?;
+    if file.metadata()?.len() > length {
+        file.set_len(length)?;
+    }
+
+    file.seek(SeekFrom::End(0))?;
+    Ok(file)
+}
+
+pub fn save<P: AsRef<Path>>(state: &ServerState, path: P) -> SnapshotResult {
+    /* Writes the machine state to a new snapshot file at path */
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&MAGIC)?;
+    serialize_into(&mut out, &VERSION)?;
+    serialize_into(&mut out, &(state.eclock.read(), state.power_on, state.no_protn,
+            state.plotter_manual, state.manual_state))?;
+    state.busy_ff.save(&mut out)?;
+    state.processor.save(&mut out)?;
+    state.store.save(&mut out)?;
+    state.bus.save(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn restore_machine(state: &mut ServerState, input: &mut dyn Read) -> SnapshotResult {
+    /* Restores everything saved ahead of the devices: the clock, the
+    console switches, the processor and the store */
+
+    let mut magic = [0_u8; 8];
+    input.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(mismatch("not a 503 snapshot file".to_string()));
+    }
+
+    let version: u32 = deserialize_from(&mut *input)?;
+    if version != VERSION {
+        return Err(mismatch(format!("snapshot version {} is not supported (expected {})",
+                version, VERSION)));
+    }
+
+    let (ticks, power_on, no_protn, plotter_manual, manual_state) = deserialize_from(&mut *input)?;
+    state.eclock.set(ticks);
+    state.power_on = power_on;
+    state.no_protn = no_protn;
+    state.plotter_manual = plotter_manual;
+    state.manual_state = manual_state;
+    state.busy_ff.restore(input)?;
+    state.processor.restore(input)?;
+    state.store.restore(input)?;
+    state.store.set_protection(!no_protn);
+    Ok(())
+}
+
+pub fn restore_store<P: AsRef<Path>>(state: &mut ServerState, path: P) -> SnapshotResult {
+    /* Restores the machine from the snapshot file but not the devices, so
+    that no device host file is opened */
+
+    restore_machine(state, &mut BufReader::new(File::open(path)?))
+}
+
+pub fn restore<P: AsRef<Path>>(state: &mut ServerState, path: P) -> SnapshotResult {
+    /* Replaces the machine state with that saved in the snapshot file,
+    leaving it unchanged if the snapshot cannot be read in full */
+
+    let mut input = BufReader::new(File::open(path)?);
+
+    // Output still buffered must reach the host files before the restored
+    // devices reopen them and cut them back
+    state.bus.flush()?;
+    let mut restored = new_state(state.pacer.speed());
+    restore_machine(&mut restored, &mut input)?;
+    restored.bus.restore(&mut input)?;
+
+    // Console clients notice a restore by the teleprinter's count of them
+    if let (Some(previous), Some(typewriter)) =
+            (state.bus.get::<Typewriter>(), restored.bus.get_mut::<Typewriter>()) {
+        typewriter.follow_restores(previous);
+    }
+
+    let old = std::mem::replace(state, restored);
+    state.monitor = old.monitor;
+    state.tracer = old.tracer;
+    state.media_dir = old.media_dir;
+    state.snapshot_dir = old.snapshot_dir;
+    state.reset_state = old.reset_state;
+    state.reset_countdown = old.reset_countdown;
+    state.pacer.restart();
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::telecode::Encoder5;
+    use super::super::pacing::Speed;
+    use super::super::typewriter::{self, PaperMark};
+
+    fn print(state: &mut ServerState, text: &str) {
+        let codes = Encoder5::new().encode_all(text).unwrap();
+        let typewriter = state.bus.get_mut::<Typewriter>().unwrap();
+        for code in codes {
+            state.eclock.advance(typewriter::CHAR_PERIOD);
+            assert!(typewriter.write_char(code));
+        }
+    }
+
+    fn printed(state: &ServerState, mark: &mut PaperMark) -> String {
+        state.bus.get::<Typewriter>().unwrap().printed_since(mark).to_string()
+    }
+
+    #[test]
+    fn clients_follow_each_restore() {
+        // After every restore, a client goes on from the end of the
+        // restored paper, however far it had read before
+        let path = std::env::temp_dir().join(format!("snapshot-{}-restores", std::process::id()));
+        let mut state = new_state(Speed::Unthrottled);
+        let mut mark = PaperMark::default();
+        print(&mut state, "AB");
+        save(&state, &path).unwrap();
+        assert_eq!(printed(&state, &mut mark), "AB");
+
+        for more in ["CDEF", "GHIJKL"].iter() {
+            print(&mut state, more);
+            assert_eq!(printed(&state, &mut mark), *more);
+            restore(&mut state, &path).unwrap();
+            assert_eq!(printed(&state, &mut mark), "");
+            print(&mut state, "X");
+            assert_eq!(printed(&state, &mut mark), "X");
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_snapshot_leaves_state() {
+        let path = std::env::temp_dir().join(format!("snapshot-{}-truncated", std::process::id()));
+        let mut state = new_state(Speed::Unthrottled);
+        state.store.poke(100, 0o1234567012345).unwrap();
+        state.processor.a_reg.set(0o17);
+        state.eclock.set(2.5);
+        save(&state, &path).unwrap();
+        let length = fs::metadata(&path).unwrap().len();
+        fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(length/2).unwrap();
+
+        let mut state = new_state(Speed::Unthrottled);
+        state.store.poke(100, 0o7654321076543).unwrap();
+        state.processor.a_reg.set(0o25);
+        state.eclock.set(7.0);
+        let result = restore(&mut state, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(state.store.peek(100), Ok(0o7654321076543));
+        assert_eq!(state.processor.a_reg.read(), 0o25);
+        assert_eq!(state.eclock.read(), 7.0);
+    }
+}
@@ -0,0 +1,307 @@
+/***********************************************************************
+* simple-tokio/src/server/store.rs
+*   Module "store" for the 503 main core store: 39-bit words held in a
+*   configurable number of 8192-word modules.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Addresses are word numbers starting at 0. The high-order bits of an
+* address select the module and the low-order 13 bits the word within
+* that module. Instruction address fields are 13 bits, so the processor
+* addresses the first module directly; all installed modules are
+* accessible to peripheral block transfers. An address beyond the
+* installed store is an error.
+*
+* Regions of the store may be protected, e.g., the area occupied by a
+* resident monitor. A write into a protected region is rejected unless
+* protection has been switched off with the NO PROTN button. Reads are
+* never restricted, nor are panel and loader accesses by peek and poke.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Save and restore the store in snapshots.
+*   Test sizing, addressing and protection.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use bincode::{serialize_into, deserialize_from};
+
+use super::register::{EmulationClock, EmulationTick, CLOCK_PERIOD};
+use super::snapshot::{self, Snapshot, SnapshotResult};
+
+pub type Address = u64;
+pub type Word = u64;
+
+pub const WORD_MASK: Word = 0o7777777777777;    // 39 bits
+pub const MODULE_BITS: u8 = 13;
+pub const MODULE_SIZE: usize = 1 << MODULE_BITS;
+pub const MAX_MODULES: usize = 16;
+pub const CYCLE_TIME: EmulationTick = 8.0*CLOCK_PERIOD;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StoreError {
+    NonExistent(Address),
+    Protected(Address)
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::NonExistent(addr) => write!(f, "non-existent store address {}", addr),
+            StoreError::Protected(addr) => write!(f, "write to protected store address {}", addr)
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+
+/***** Store *****/
+
+pub struct Store {
+    clock: Arc<EmulationClock>,
+    modules: usize,
+    words: Vec<Word>,
+    cycles: u64,
+    protected: Vec<RangeInclusive<Address>>,
+    protection: bool
+}
+
+impl Store {
+
+    pub fn new(modules: usize, clock: Arc<EmulationClock>) -> Self {
+        /* Creates a cleared store of the specified number of modules */
+
+        let modules = modules.clamp(1, MAX_MODULES);
+        Store {
+            clock,
+            modules,
+            words: vec![0; modules*MODULE_SIZE],
+            cycles: 0,
+            protected: Vec::new(),
+            protection: true
+        }
+    }
+
+    pub fn modules(&self) -> usize {
+        self.modules
+    }
+
+    pub fn size(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn module_of(addr: Address) -> usize {
+        (addr >> MODULE_BITS) as usize
+    }
+
+    fn index(&self, addr: Address) -> Result<usize, StoreError> {
+        if (addr as usize) < self.words.len() {
+            Ok(addr as usize)
+        } else {
+            Err(StoreError::NonExistent(addr))
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.cycles += 1;
+        self.clock.advance(CYCLE_TIME);
+    }
+
+    pub fn read(&mut self, addr: Address) -> Result<Word, StoreError> {
+        /* Reads a word, taking one store cycle */
+
+        let x = self.index(addr)?;
+        self.cycle();
+        Ok(self.words[x])
+    }
+
+    pub fn write(&mut self, addr: Address, word: Word) -> Result<(), StoreError> {
+        /* Writes a word, taking one store cycle */
+
+        let x = self.index(addr)?;
+        if self.is_protected(addr) {
+            return Err(StoreError::Protected(addr));
+        }
+
+        self.cycle();
+        self.words[x] = word & WORD_MASK;
+        Ok(())
+    }
+
+    pub fn peek(&self, addr: Address) -> Result<Word, StoreError> {
+        /* Reads a word without taking emulated time, for panels and loaders */
+
+        let x = self.index(addr)?;
+        Ok(self.words[x])
+    }
+
+    pub fn poke(&mut self, addr: Address, word: Word) -> Result<(), StoreError> {
+        /* Writes a word without taking emulated time, for panels and loaders */
+
+        let x = self.index(addr)?;
+        self.words[x] = word & WORD_MASK;
+        Ok(())
+    }
+
+    pub fn protect(&mut self, first: Address, last: Address) {
+        /* Adds a protected region from first through last */
+        self.protected.push(first..=last);
+    }
+
+    pub fn unprotect_all(&mut self) {
+        self.protected.clear();
+    }
+
+    pub fn protected_regions(&self) -> &[RangeInclusive<Address>] {
+        &self.protected
+    }
+
+    pub fn set_protection(&mut self, on: bool) {
+        /* Enables or disables checking of the protected regions */
+        self.protection = on;
+    }
+
+    pub fn is_protected(&self, addr: Address) -> bool {
+        self.protection && self.protected.iter().any(|r| r.contains(&addr))
+    }
+
+    pub fn clear(&mut self) {
+        for w in self.words.iter_mut() {
+            *w = 0;
+        }
+    }
+} // impl Store
+
+impl Snapshot for Store {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        let protected: Vec<(Address, Address)> = self.protected.iter()
+                .map(|r| (*r.start(), *r.end()))
+                .collect();
+        serialize_into(out, &(&self.words, protected, self.protection, self.cycles))
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (words, protected, protection, cycles): (Vec<Word>, Vec<(Address, Address)>, bool, u64) =
+                deserialize_from(input)?;
+        if words.len() != self.words.len() {
+            return Err(snapshot::mismatch(format!("snapshot store has {} words, this machine {}",
+                    words.len(), self.words.len())));
+        }
+
+        self.words = words;
+        self.protected = protected.into_iter().map(|(first, last)| first..=last).collect();
+        self.protection = protection;
+        self.cycles = cycles;
+        Ok(())
+    }
+} // impl Snapshot for Store
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{new_state, run_slice};
+    use super::super::pacing::Speed;
+    use super::super::processor::{instruction, word, StopReason};
+
+    fn store(modules: usize) -> Store {
+        Store::new(modules, Arc::new(EmulationClock::new(0.0)))
+    }
+
+    #[test]
+    fn module_sizing() {
+        assert_eq!((store(1).modules(), store(1).size()), (1, MODULE_SIZE));
+        assert_eq!((store(3).modules(), store(3).size()), (3, 3*MODULE_SIZE));
+        assert_eq!(store(0).modules(), 1);
+        assert_eq!(store(MAX_MODULES + 1).modules(), MAX_MODULES);
+        assert_eq!(Store::module_of(MODULE_SIZE as Address - 1), 0);
+        assert_eq!(Store::module_of(MODULE_SIZE as Address), 1);
+    }
+
+    #[test]
+    fn addresses_do_not_wrap() {
+        // An address beyond the installed modules is an error, not a
+        // reference to a word of the first module
+        let mut s = store(2);
+        let end = s.size() as Address;
+        s.write(end - 1, 0o17).unwrap();
+        assert_eq!(s.read(end - 1), Ok(0o17));
+        assert_eq!(s.read(end), Err(StoreError::NonExistent(end)));
+        assert_eq!(s.write(end, 1), Err(StoreError::NonExistent(end)));
+        assert_eq!(s.peek(end), Err(StoreError::NonExistent(end)));
+        assert_eq!(s.poke(end, 1), Err(StoreError::NonExistent(end)));
+        assert_eq!(s.peek(0), Ok(0));
+
+        // Only the 39 bits of a word are kept
+        s.write(5, WORD_MASK + 2).unwrap();
+        assert_eq!(s.peek(5), Ok(1));
+        assert_eq!(s.cycles(), 3);
+    }
+
+    #[test]
+    fn protected_regions() {
+        let mut s = store(1);
+        s.protect(100, 109);
+        s.protect(200, 200);
+        assert_eq!(s.write(99, 1), Ok(()));
+        assert_eq!(s.write(100, 1), Err(StoreError::Protected(100)));
+        assert_eq!(s.write(109, 1), Err(StoreError::Protected(109)));
+        assert_eq!(s.write(110, 1), Ok(()));
+        assert_eq!(s.write(200, 1), Err(StoreError::Protected(200)));
+        assert_eq!((s.peek(100), s.peek(200)), (Ok(0), Ok(0)));
+
+        // Refused writes take no store cycle; reads are not restricted
+        assert_eq!(s.cycles(), 2);
+        assert_eq!(s.read(100), Ok(0));
+
+        // NO PROTN lets the program write anywhere until it is released
+        s.set_protection(false);
+        assert!(!s.is_protected(100));
+        assert_eq!(s.write(100, 2), Ok(()));
+        s.set_protection(true);
+        assert_eq!(s.write(101, 2), Err(StoreError::Protected(101)));
+
+        s.unprotect_all();
+        assert_eq!(s.write(101, 2), Ok(()));
+    }
+
+    #[test]
+    fn peek_and_poke_ignore_protection() {
+        let mut s = store(1);
+        s.protect(0, 10);
+        assert_eq!(s.poke(5, 0o123), Ok(()));
+        assert_eq!(s.peek(5), Ok(0o123));
+        assert_eq!(s.cycles(), 0);
+    }
+
+    #[test]
+    fn protected_write_stops_processor() {
+        // Word 0 stores A into word 100, which is protected
+        let mut state = new_state(Speed::Unthrottled);
+        state.power_on = true;
+        state.store.protect(100, 100);
+        state.store.poke(0, word(instruction(0o20, 100), false, instruction(0o40, 0))).unwrap();
+        state.processor.a_reg.set(0o5);
+        state.processor.running = true;
+
+        run_slice(&mut state);
+        assert!(!state.processor.running);
+        assert_eq!(state.last_stop, Some(StopReason::Protected(100)));
+        assert_eq!(state.error_glow, 1.0);
+        assert_eq!(state.store.peek(100), Ok(0));
+    }
+}
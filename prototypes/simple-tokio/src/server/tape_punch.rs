@@ -0,0 +1,297 @@
+/***********************************************************************
+* simple-tokio/src/server/tape_punch.rs
+*   Module "tape_punch" for the 503 paper tape punch. Punched frames are
+*   written to a host file as raw bytes, one tape frame per byte.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Implement the Device trait.
+*   Report when the device will next be ready.
+*   Save and restore the device state in snapshots.
+*   Discard frames punched after the snapshot when restoring one.
+*   Stop the punch on CLEAR.
+*   Flush the output through the Device trait, for snapshot restores.
+*   Test the punched frames and runout.
+*   Take the file names sent by clients within the media directory.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use super::device::{CommandResult, Device};
+use super::media;
+use super::register::{EmulationClock, EmulationTick, FlipFlop};
+use super::snapshot::{self, Snapshot, SnapshotResult};
+
+pub const CHAR_PERIOD: EmulationTick = 1.0/300.0;      // sec, 300 ch/s
+pub const RUNOUT_FRAMES: usize = 120;                   // blank frames, 12 inches of tape
+
+// Status word bits returned by the 77 function
+pub const STATUS_MOUNTED: u64 = 1;
+
+pub struct TapePunch {
+    clock: Arc<EmulationClock>,
+    name: String,
+    file: Option<BufWriter<File>>,
+    frames: usize,
+    next_ready: EmulationTick,
+    pub transfer_ff: FlipFlop
+}
+
+impl TapePunch {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        TapePunch {
+            clock: clock.clone(),
+            name: String::new(),
+            file: None,
+            frames: 0,
+            next_ready: 0.0,
+            transfer_ff: FlipFlop::new(clock)
+        }
+    }
+
+    pub fn mount<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+        /* Starts a new tape, creating or truncating the host file */
+
+        self.unload()?;
+        self.file = Some(BufWriter::new(File::create(&path)?));
+        self.name = path.as_ref().display().to_string();
+        self.frames = 0;
+        Ok(())
+    }
+
+    pub fn unload(&mut self) -> std::io::Result<()> {
+        /* Tears off the tape, flushing it to the host file */
+
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+
+        self.name.clear();
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(())
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    fn punch(&mut self, frames: &[u8]) {
+        /* Punches frames and advances the time at which the punch will next
+        be ready. Without a tape, the frames are lost */
+
+        let now = self.clock.read();
+        self.next_ready = self.next_ready.max(now) + CHAR_PERIOD*frames.len() as f64;
+        self.transfer_ff.set(true);
+        if let Some(file) = self.file.as_mut() {
+            if let Err(e) = file.write_all(frames) {
+                println!("tape punch {} write error: {}", self.name, e);
+            }
+
+            self.frames += frames.len();
+        }
+    }
+
+    pub fn write_char(&mut self, ch: u8) -> bool {
+        /* Punches one frame, returning false if the punch is still busy with
+        the previous one */
+
+        if self.clock.read() < self.next_ready {
+            false
+        } else {
+            self.punch(&[ch]);
+            true
+        }
+    }
+
+    pub fn runout(&mut self, count: usize) -> bool {
+        /* Punches count blank frames of leader or trailer, or the standard
+        runout if count is zero */
+
+        if self.clock.read() < self.next_ready {
+            false
+        } else {
+            let count = if count == 0 {RUNOUT_FRAMES} else {count};
+            self.punch(&vec![0; count]);
+            if let Err(e) = self.flush() {
+                println!("tape punch {} flush error: {}", self.name, e);
+            }
+
+            true
+        }
+    }
+
+    pub fn status(&self) -> u64 {
+        if self.file.is_some() {STATUS_MOUNTED} else {0}
+    }
+
+    pub fn update_lamps(&mut self) {
+        let moving = self.clock.read() < self.next_ready;
+        if moving != self.transfer_ff.read() {
+            self.transfer_ff.set(moving);
+        } else {
+            self.transfer_ff.update_glow(0.0);
+        }
+    }
+} // impl TapePunch
+
+impl Device for TapePunch {
+
+    fn name(&self) -> &str {
+        "PUNCH"
+    }
+
+    fn write_char(&mut self, ch: u8) -> bool {
+        TapePunch::write_char(self, ch)
+    }
+
+    fn control(&mut self, order: u64) -> bool {
+        self.runout(order as usize)
+    }
+
+    fn status(&self) -> u64 {
+        TapePunch::status(self)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        Some(self.next_ready)
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], media: &Path) -> Option<CommandResult> {
+        match code {
+            "TPMNT" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|name: String| media::resolve(media, &name)
+                        .and_then(|path| self.mount(path)).map_err(Into::into))),
+            "TPUNL" => Some(self.unload().map_err(Into::into)),
+            "TPRUN" => {
+                self.runout(0);
+                Some(Ok(()))
+            }
+            _ => None
+        }
+    }
+
+    fn clear(&mut self) {
+        self.next_ready = 0.0;
+        self.transfer_ff.set(false);
+    }
+
+    fn power(&mut self, _on: bool) {
+        if let Err(e) = self.flush() {
+            println!("Error flushing tape punch: {}", e);
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        TapePunch::flush(self)
+    }
+
+    fn update_lamps(&mut self) {
+        TapePunch::update_lamps(self);
+    }
+
+    fn glow(&self) -> f32 {
+        *self.transfer_ff.read_glow()
+    }
+
+    fn transfer_glow(&self) -> f32 {
+        *self.transfer_ff.read_glow()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for TapePunch
+
+impl Snapshot for TapePunch {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(&mut *out, &(&self.name, self.frames, self.next_ready))?;
+        self.transfer_ff.save(out)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (name, frames, next_ready): (String, usize, EmulationTick) = deserialize_from(&mut *input)?;
+        self.unload()?;
+        if !name.is_empty() {
+            // The file holds only this tape, so its length is the frame count
+            self.file = Some(BufWriter::new(snapshot::reopen(&name, frames as u64)?));
+        }
+
+        self.name = name;
+        self.frames = frames;
+        self.next_ready = next_ready;
+        self.transfer_ff.restore(input)
+    }
+} // impl Snapshot for TapePunch
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn punched_frames() {
+        let path = std::env::temp_dir().join(format!("punch-{}-frames", std::process::id()));
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut punch = TapePunch::new(clock.clone());
+        punch.mount(&path).unwrap();
+        assert_eq!(punch.status(), STATUS_MOUNTED);
+
+        // Leader, two frames at the punching rate, and a short trailer
+        assert!(punch.runout(0));
+        assert_eq!(Device::ready_time(&punch), Some(RUNOUT_FRAMES as f64*CHAR_PERIOD));
+        assert!(!punch.write_char(0o21));
+        clock.advance(RUNOUT_FRAMES as f64*CHAR_PERIOD);
+        assert!(punch.write_char(0o21));
+        assert!(!punch.write_char(0o22));
+        clock.advance(CHAR_PERIOD);
+        assert!(punch.write_char(0o22));
+        clock.advance(CHAR_PERIOD);
+        assert!(Device::control(&mut punch, 3));
+        assert_eq!(punch.frames(), RUNOUT_FRAMES + 5);
+
+        punch.unload().unwrap();
+        assert_eq!(punch.status(), 0);
+        let mut expected = vec![0; RUNOUT_FRAMES];
+        expected.extend_from_slice(&[0o21, 0o22, 0, 0, 0]);
+        assert_eq!(fs::read(&path).unwrap(), expected);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn frames_lost_without_tape() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut punch = TapePunch::new(clock.clone());
+        assert!(punch.write_char(0o7));
+        assert_eq!(punch.frames(), 0);
+        assert!(!punch.write_char(0o7));
+    }
+}
@@ -0,0 +1,270 @@
+/***********************************************************************
+* simple-tokio/src/server/tape_reader.rs
+*   Module "tape_reader" for the 503 paper tape reader. Tapes are host
+*   files of raw bytes, one tape frame per byte.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Implement the Device trait.
+*   Report when the device will next be ready.
+*   Save and restore the device state in snapshots.
+*   Stop the tape on CLEAR.
+*   Test the reading rate and the end of tape.
+*   Take the file names sent by clients within the media directory.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use super::device::{CommandResult, Device};
+use super::media;
+use super::register::{EmulationClock, EmulationTick, FlipFlop};
+use super::snapshot::{Snapshot, SnapshotResult};
+
+pub const CHAR_PERIOD: EmulationTick = 1.0/1000.0;     // sec, 1000 ch/s
+
+// Status word bits returned by the 77 function
+pub const STATUS_MOUNTED: u64 = 1;
+pub const STATUS_END_OF_TAPE: u64 = 2;
+
+pub struct TapeReader {
+    clock: Arc<EmulationClock>,
+    name: String,
+    tape: Vec<u8>,
+    position: usize,
+    next_ready: EmulationTick,
+    pub transfer_ff: FlipFlop
+}
+
+impl TapeReader {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        TapeReader {
+            clock: clock.clone(),
+            name: String::new(),
+            tape: Vec::new(),
+            position: 0,
+            next_ready: 0.0,
+            transfer_ff: FlipFlop::new(clock)
+        }
+    }
+
+    pub fn mount<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+        /* Loads a tape image from a host file and positions it at the start */
+
+        self.tape = fs::read(&path)?;
+        self.name = path.as_ref().display().to_string();
+        self.position = 0;
+        Ok(())
+    }
+
+    pub fn mount_bytes(&mut self, name: &str, tape: Vec<u8>) {
+        /* Loads a tape image already held in memory */
+
+        self.tape = tape;
+        self.name = name.to_string();
+        self.position = 0;
+    }
+
+    pub fn unload(&mut self) {
+        self.tape.clear();
+        self.name.clear();
+        self.position = 0;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.tape.len() - self.position
+    }
+
+    pub fn read_char(&mut self) -> Option<u8> {
+        /* Returns the next frame if the reader has had time to move the tape
+        to it, otherwise None. A reader that has been idle must first start
+        the tape, taking one character period */
+
+        if self.position >= self.tape.len() {
+            self.transfer_ff.set(false);
+            return None;
+        }
+
+        let now = self.clock.read();
+        if now >= self.next_ready + CHAR_PERIOD {
+            self.next_ready = now + CHAR_PERIOD;            // reader was stopped
+        }
+
+        self.transfer_ff.set(true);
+        if now < self.next_ready {
+            None
+        } else {
+            let ch = self.tape[self.position];
+            self.position += 1;
+            self.next_ready += CHAR_PERIOD;
+            Some(ch)
+        }
+    }
+
+    pub fn status(&self) -> u64 {
+        let mut status = 0;
+        if !self.name.is_empty() {
+            status |= STATUS_MOUNTED;
+        }
+
+        if self.position >= self.tape.len() {
+            status |= STATUS_END_OF_TAPE;
+        }
+
+        status
+    }
+
+    pub fn update_lamps(&mut self) {
+        /* Turns TRANSFER off once the tape is no longer moving */
+
+        let moving = self.clock.read() < self.next_ready;
+        if moving != self.transfer_ff.read() {
+            self.transfer_ff.set(moving);
+        } else {
+            self.transfer_ff.update_glow(0.0);
+        }
+    }
+} // impl TapeReader
+
+impl Device for TapeReader {
+
+    fn name(&self) -> &str {
+        "READER"
+    }
+
+    fn read_char(&mut self) -> Option<u8> {
+        TapeReader::read_char(self)
+    }
+
+    fn status(&self) -> u64 {
+        TapeReader::status(self)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        if self.position < self.tape.len() {Some(self.next_ready)} else {None}
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], media: &Path) -> Option<CommandResult> {
+        match code {
+            "TRMNT" => Some(deserialize(payload).map_err(Into::into)
+                    .and_then(|name: String| media::resolve(media, &name)
+                        .and_then(|path| self.mount(path)).map_err(Into::into))),
+            "TRUNL" => {
+                self.unload();
+                Some(Ok(()))
+            }
+            _ => None
+        }
+    }
+
+    fn clear(&mut self) {
+        self.next_ready = 0.0;          // stopped, so the next read restarts the tape
+        self.transfer_ff.set(false);
+    }
+
+    fn update_lamps(&mut self) {
+        TapeReader::update_lamps(self);
+    }
+
+    fn glow(&self) -> f32 {
+        *self.transfer_ff.read_glow()
+    }
+
+    fn transfer_glow(&self) -> f32 {
+        *self.transfer_ff.read_glow()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for TapeReader
+
+impl Snapshot for TapeReader {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(&mut *out, &(&self.name, &self.tape, self.position, self.next_ready))?;
+        self.transfer_ff.save(out)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (name, tape, position, next_ready) = deserialize_from(&mut *input)?;
+        self.name = name;
+        self.tape = tape;
+        self.position = position;
+        self.next_ready = next_ready;
+        self.transfer_ff.restore(input)
+    }
+} // impl Snapshot for TapeReader
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_rate() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut reader = TapeReader::new(clock.clone());
+        reader.mount_bytes("test", vec![1, 2, 3]);
+
+        // One frame each character period while the tape keeps moving
+        assert_eq!(reader.read_char(), Some(1));
+        assert_eq!(reader.read_char(), None);
+        assert_eq!(Device::ready_time(&reader), Some(CHAR_PERIOD));
+        clock.advance(CHAR_PERIOD);
+        assert_eq!(reader.read_char(), Some(2));
+
+        // A reader left idle takes a period to start the tape again
+        clock.advance(5.0*CHAR_PERIOD);
+        assert_eq!(reader.read_char(), None);
+        clock.advance(CHAR_PERIOD);
+        assert_eq!(reader.read_char(), Some(3));
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn end_of_tape() {
+        let clock = Arc::new(EmulationClock::new(0.0));
+        let mut reader = TapeReader::new(clock.clone());
+        assert_eq!(reader.status(), STATUS_END_OF_TAPE);
+        assert_eq!(reader.read_char(), None);
+
+        reader.mount_bytes("test", vec![0o17]);
+        assert_eq!(reader.status(), STATUS_MOUNTED);
+        assert_eq!(reader.read_char(), Some(0o17));
+        assert_eq!(reader.status(), STATUS_MOUNTED | STATUS_END_OF_TAPE);
+
+        // Past the end, the reader waits for a tape that will never come
+        clock.advance(CHAR_PERIOD);
+        assert_eq!(reader.read_char(), None);
+        assert_eq!(Device::ready_time(&reader), None);
+        assert_eq!(reader.remaining(), 0);
+
+        reader.unload();
+        assert_eq!(reader.status(), STATUS_END_OF_TAPE);
+    }
+}
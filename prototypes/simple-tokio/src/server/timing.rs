@@ -0,0 +1,72 @@
+/***********************************************************************
+* simple-tokio/src/server/timing.rs
+*   Module "timing" holds the execution times of the 503 instructions,
+*   used by the processor to advance the emulation clock.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Times are in processor clock periods (CLOCK_PERIOD, 0.3 µs) and are
+* exclusive of store cycles: the store advances the clock itself by
+* CYCLE_TIME (8 clock periods) for each word read or written, so an
+* instruction's full time is its execution time plus the cycles for
+* the instruction fetch (once per word), the operand, and any store of
+* a result. Peripheral waits and block-transfer word times are also
+* accounted separately, by the devices.
+*
+* Multiplication, division and shifts proceed one digit per step, so
+* their times depend on the number of digits or places. The figures
+* are approximate, based on the published 503 instruction times.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use super::register::{EmulationTick, CLOCK_PERIOD};
+
+pub const ARITHMETIC: u32 = 6;          // groups 0-3
+pub const JUMP: u32 = 4;                // group 4
+pub const SHIFT_SETUP: u32 = 4;         // 50, 51, 54, 55, plus one per place
+pub const MULTIPLY_SETUP: u32 = 4;      // 52, 53, plus two per digit
+pub const DIVIDE_SETUP: u32 = 4;        // 56, plus four per digit
+pub const TRANSFER_AR: u32 = 4;         // 57
+pub const FLOAT_ADD: u32 = 60;          // 60, 61, 62
+pub const FLOAT_MULTIPLY: u32 = 100;    // 63
+pub const FLOAT_DIVIDE: u32 = 180;      // 64
+pub const STANDARDIZE: u32 = 42;        // 65
+pub const INPUT_OUTPUT: u32 = 6;        // group 7
+pub const B_MODIFY: u32 = 3;            // adding n to the second instruction
+
+const DIGITS: u32 = 38;                 // multiplier and quotient digits
+
+pub fn clock_periods(func: u64, addr: u64) -> u32 {
+    /* Returns the number of clock periods to execute function func with
+    address addr, exclusive of store cycles */
+
+    let places = (addr & 0o177) as u32;
+    match func {
+        0o00..=0o37 => ARITHMETIC,
+        0o40..=0o47 => JUMP,
+        0o50 | 0o51 | 0o54 | 0o55 => SHIFT_SETUP + places,
+        0o52 | 0o53 => MULTIPLY_SETUP + 2*DIGITS,
+        0o56 => DIVIDE_SETUP + 4*DIGITS,
+        0o57 => TRANSFER_AR,
+        0o60..=0o62 => FLOAT_ADD,
+        0o63 => FLOAT_MULTIPLY,
+        0o64 => FLOAT_DIVIDE,
+        0o65 => STANDARDIZE,
+        0o66 | 0o67 => 0,
+        _ => INPUT_OUTPUT
+    }
+}
+
+pub fn execute_time(func: u64, addr: u64) -> EmulationTick {
+    clock_periods(func, addr) as EmulationTick * CLOCK_PERIOD
+}
+
+pub fn b_modify_time() -> EmulationTick {
+    B_MODIFY as EmulationTick * CLOCK_PERIOD
+}
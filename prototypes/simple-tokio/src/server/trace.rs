@@ -0,0 +1,203 @@
+/***********************************************************************
+* simple-tokio/src/server/trace.rs
+*   Module "trace" records each instruction the processor executes to a
+*   compact binary file, and lists such a file as text, for comparing
+*   a run with listings from the real machine.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* A trace file begins with the eight bytes "E503TRCE" and a version
+* number, followed by one fixed-length bincode record per instruction:
+*       f64     emulated time after the instruction, sec
+*       u16     SCR, the word address of the instruction
+*       bool    second instruction of the word
+*       u32     instruction as executed, after any B-modification
+*       bool    instruction was B-modified
+*       u64     A after the instruction
+*       u64     AR after the instruction
+* The effective address is the address field of the instruction as
+* executed. Recording and listing can each be limited to instructions
+* whose SCR is within an address range.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Show instructions through the disassembler.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use bincode::{serialize_into, deserialize_from};
+
+use super::Result;
+use super::disasm;
+use super::processor::{self, Executed, Processor};
+use super::register::EmulationTick;
+use super::store::{Address, Word};
+
+pub const MAGIC: [u8; 8] = *b"E503TRCE";
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Record {
+    pub time: EmulationTick,
+    pub addr: Address,
+    pub second: bool,
+    pub instr: u64,
+    pub modified: bool,
+    pub a: Word,
+    pub ar: Word
+}
+
+impl Record {
+
+    pub fn effective_address(&self) -> Address {
+        processor::address(self.instr)
+    }
+
+    fn write(&self, out: &mut dyn Write) -> bincode::Result<()> {
+        serialize_into(out, &(self.time, self.addr as u16, self.second, self.instr as u32,
+                self.modified, self.a, self.ar))
+    }
+
+    fn read(input: &mut dyn Read) -> bincode::Result<Self> {
+        let (time, addr, second, instr, modified, a, ar): (f64, u16, bool, u32, bool, u64, u64) =
+                deserialize_from(input)?;
+        Ok(Record {time, addr: addr as Address, second, instr: instr as u64, modified, a, ar})
+    }
+} // impl Record
+
+pub fn parse_range(text: &str) -> Result<RangeInclusive<Address>> {
+    /* Parses an address range written "first-last", or a single address */
+
+    match text.split_once('-') {
+        Some((first, last)) => Ok(first.parse()?..=last.parse()?),
+        None => {
+            let addr = text.parse()?;
+            Ok(addr..=addr)
+        }
+    }
+}
+
+
+/***** Tracer *****/
+
+pub struct Tracer {
+    out: BufWriter<File>,
+    range: Option<RangeInclusive<Address>>,
+    records: u64
+}
+
+impl Tracer {
+
+    pub fn create<P: AsRef<Path>>(path: P, range: Option<RangeInclusive<Address>>) -> bincode::Result<Self> {
+        /* Starts a new trace file, recording only instructions whose SCR
+        is within range, if one is given */
+
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(&MAGIC)?;
+        serialize_into(&mut out, &VERSION)?;
+        Ok(Tracer {out, range, records: 0})
+    }
+
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    pub fn record(&mut self, time: EmulationTick, executed: Executed, processor: &Processor) -> bincode::Result<()> {
+        if let Some(range) = &self.range {
+            if !range.contains(&executed.addr) {
+                return Ok(());
+            }
+        }
+
+        let record = Record {
+            time,
+            addr: executed.addr,
+            second: executed.second,
+            instr: executed.instr,
+            modified: executed.modified,
+            a: processor.a_reg.read(),
+            ar: processor.ar_reg.read()
+        };
+
+        self.records += 1;
+        record.write(&mut self.out)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+} // impl Tracer
+
+
+/***** Trace listing *****/
+
+pub fn format_record(record: &Record) -> String {
+    format!("{:12.7}  {:5}.{}  {}{}  ea {:4}  A {:013o}  AR {:013o}",
+            record.time, record.addr, if record.second {2} else {1},
+            disasm::format_instruction(record.instr),
+            if record.modified {'+'} else {' '}, record.effective_address(),
+            record.a, record.ar)
+}
+
+pub fn dump<P: AsRef<Path>>(path: P, range: Option<RangeInclusive<Address>>,
+        out: &mut dyn Write) -> Result<u64> {
+    /* Lists the records of a trace file whose SCR is within range, if one
+    is given, returning the number listed */
+
+    let mut input = BufReader::new(File::open(path)?);
+    let mut magic = [0_u8; 8];
+    input.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err("not a 503 trace file".into());
+    }
+
+    let version: u32 = deserialize_from(&mut input)?;
+    if version != VERSION {
+        return Err(format!("trace version {} is not supported (expected {})", version, VERSION).into());
+    }
+
+    let mut count = 0;
+    loop {
+        let record = match Record::read(&mut input) {
+            Ok(record) => record,
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref ie) if ie.kind() == io::ErrorKind::UnexpectedEof => break,
+                _ => return Err(e.into())
+            }
+        };
+
+        let listed = match &range {
+            Some(r) => r.contains(&record.addr),
+            None => true
+        };
+
+        if listed {
+            writeln!(out, "{}", format_record(&record))?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+pub fn main(args: &[&str]) -> Result<()> {
+    /* Lists a trace file on stdout: trace-file [first-last] */
+
+    let (path, range) = match args {
+        [path] => (path, None),
+        [path, range] => (path, Some(parse_range(range)?)),
+        _ => return Err("Usage: simple-tokio trace trace-file [first-last]".into())
+    };
+
+    let stdout = io::stdout();
+    dump(path, range, &mut stdout.lock())?;
+    Ok(())
+}
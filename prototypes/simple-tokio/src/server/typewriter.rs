@@ -0,0 +1,280 @@
+/***********************************************************************
+* simple-tokio/src/server/typewriter.rs
+*   Module "typewriter" for the 503 console teleprinter. Characters
+*   are exchanged with the processor in 5-hole telecode and with the
+*   attached consoles as Unicode text.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Use the telecode module for character translation.
+*   Implement the Device trait.
+*   Report when the device will next be ready.
+*   Save and restore the device state in snapshots.
+*   Release the hold and any character in progress on CLEAR.
+*   Keep clients' places in the printed text across snapshot restores.
+*   Split printed text sent to clients to fit the message frame lengths.
+*   Carry the restore count over to the teleprinter of a restored machine.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use bincode::{deserialize, serialize_into, deserialize_from};
+
+use crate::telecode::{Decoder5, Encoder5, CARRIAGE_RETURN};
+use super::device::{CommandResult, Device};
+use super::register::{EmulationClock, EmulationTick, FlipFlop};
+use super::snapshot::{Snapshot, SnapshotResult};
+
+pub const CHAR_PERIOD: EmulationTick = 1.0/10.0;       // sec, 10 ch/s
+pub const FRAME_TEXT: usize = 32768;    // bytes of text sent in one message frame
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PaperMark {
+    restores: u64,                      // snapshot restores seen
+    position: usize                     // bytes of paper already seen
+}
+
+pub struct Typewriter {
+    clock: Arc<EmulationClock>,
+    printer: Decoder5,                  // printer shift state
+    keys: Encoder5,                     // keyboard shift state
+    keyboard: VecDeque<u8>,             // telecode typed but not yet read
+    paper: String,                      // everything printed so far
+    next_ready: EmulationTick,
+    key_wait: bool,                     // held waiting for the operator to type
+    restores: u64,                      // times the paper was replaced from a snapshot
+    restored_at: usize,                 // length of the paper at the last restore
+    pub hold_ff: FlipFlop
+}
+
+impl Typewriter {
+
+    pub fn new(clock: Arc<EmulationClock>) -> Self {
+        Typewriter {
+            clock: clock.clone(),
+            printer: Decoder5::new(),
+            keys: Encoder5::new(),
+            keyboard: VecDeque::new(),
+            paper: String::new(),
+            next_ready: 0.0,
+            key_wait: false,
+            restores: 0,
+            restored_at: 0,
+            hold_ff: FlipFlop::new(clock)
+        }
+    }
+
+    pub fn paper(&self) -> &str {
+        &self.paper
+    }
+
+    pub fn printed_since(&self, mark: &mut PaperMark) -> &str {
+        /* Returns the text printed since a client last looked, and moves its
+        mark to the end of the paper. After a snapshot restore the client
+        goes on from the end of the restored paper */
+
+        if mark.restores != self.restores {
+            *mark = PaperMark {restores: self.restores, position: self.restored_at};
+        }
+
+        let mut start = mark.position.min(self.paper.len());
+        while !self.paper.is_char_boundary(start) {
+            start -= 1;
+        }
+
+        mark.position = self.paper.len();
+        &self.paper[start..]
+    }
+
+    pub fn follow_restores(&mut self, previous: &Typewriter) {
+        /* Counts a restore into this new teleprinter on from the restores of
+        the one it replaces, so clients see each restore as a new one */
+
+        self.restores = previous.restores + 1;
+    }
+
+    pub fn frames(text: &str) -> impl Iterator<Item = &str> {
+        /* Splits printed text into pieces small enough for the 16-bit
+        lengths of a message frame, breaking only between characters */
+
+        let mut rest = text;
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+
+            let mut end = rest.len().min(FRAME_TEXT);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let (piece, tail) = rest.split_at(end);
+            rest = tail;
+            Some(piece)
+        })
+    }
+
+    fn hold(&mut self, waiting: bool) {
+        if waiting != self.hold_ff.read() {
+            self.hold_ff.set(waiting);
+        }
+    }
+
+    pub fn write_char(&mut self, ch: u8) -> bool {
+        /* Prints one telecode character, returning false if the teleprinter
+        is still busy with the previous one */
+
+        let now = self.clock.read();
+        self.key_wait = false;
+        if now < self.next_ready {
+            self.hold(true);
+            return false;
+        }
+
+        self.hold(false);
+        self.next_ready = now + CHAR_PERIOD;
+        match self.printer.decode(ch) {
+            Some('\r') | None => {}
+            Some(c) => self.paper.push(c)
+        }
+
+        true
+    }
+
+    pub fn read_char(&mut self) -> Option<u8> {
+        /* Returns the next telecode character typed by the operator, or None
+        if the machine must wait for a key */
+
+        let ch = self.keyboard.pop_front();
+        self.key_wait = ch.is_none();
+        self.hold(self.key_wait);
+        ch
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        /* Queues operator keystrokes, inserting shift characters as needed.
+        Characters with no telecode equivalent are ignored */
+
+        let mut codes = Vec::new();
+        for c in text.chars() {
+            if c == '\n' {
+                codes.push(CARRIAGE_RETURN);
+            }
+            self.keys.encode(c, &mut codes).ok();
+        }
+
+        self.keyboard.extend(codes);
+    }
+
+    pub fn status(&self) -> u64 {
+        /* Bit 0: keyboard has characters waiting */
+        if self.keyboard.is_empty() {0} else {1}
+    }
+
+    pub fn update_lamps(&mut self) {
+        self.hold_ff.update_glow(0.0);
+    }
+} // impl Typewriter
+
+impl Device for Typewriter {
+
+    fn name(&self) -> &str {
+        "TYPEWRITER"
+    }
+
+    fn read_char(&mut self) -> Option<u8> {
+        Typewriter::read_char(self)
+    }
+
+    fn write_char(&mut self, ch: u8) -> bool {
+        Typewriter::write_char(self, ch)
+    }
+
+    fn status(&self) -> u64 {
+        Typewriter::status(self)
+    }
+
+    fn ready_time(&self) -> Option<EmulationTick> {
+        if self.key_wait {None} else {Some(self.next_ready)}
+    }
+
+    fn command(&mut self, code: &str, payload: &[u8], _media: &Path) -> Option<CommandResult> {
+        match code {
+            "KEYIN" => Some(deserialize(payload).map_err(Into::into)
+                    .map(|text: String| self.type_text(&text))),
+            _ => None
+        }
+    }
+
+    fn clear(&mut self) {
+        self.next_ready = 0.0;
+        self.key_wait = false;
+        self.hold(false);
+    }
+
+    fn update_lamps(&mut self) {
+        Typewriter::update_lamps(self);
+    }
+
+    fn glow(&self) -> f32 {
+        *self.hold_ff.read_glow()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+} // impl Device for Typewriter
+
+impl Snapshot for Typewriter {
+
+    fn save(&self, out: &mut dyn Write) -> SnapshotResult {
+        serialize_into(&mut *out, &(self.printer.figures, self.keys.figures, &self.keyboard,
+                &self.paper, self.next_ready, self.key_wait))?;
+        self.hold_ff.save(out)
+    }
+
+    fn restore(&mut self, input: &mut dyn Read) -> SnapshotResult {
+        let (printer_figures, keys_figures, keyboard, paper, next_ready, key_wait) =
+                deserialize_from(&mut *input)?;
+        self.printer.figures = printer_figures;
+        self.keys.figures = keys_figures;
+        self.keyboard = keyboard;
+        self.paper = paper;
+        self.next_ready = next_ready;
+        self.key_wait = key_wait;
+        self.restores += 1;
+        self.restored_at = self.paper.len();
+        self.hold_ff.restore(input)
+    }
+} // impl Snapshot for Typewriter
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_split_between_characters() {
+        let text = "a".repeat(FRAME_TEXT - 1) + "\u{bd}" + &"b".repeat(FRAME_TEXT + 5);
+        let pieces: Vec<&str> = Typewriter::frames(&text).collect();
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[0].len(), FRAME_TEXT - 1);
+        assert!(pieces.iter().all(|p| p.len() <= FRAME_TEXT));
+        assert_eq!(pieces.concat(), text);
+        assert_eq!(Typewriter::frames("").count(), 0);
+    }
+}
@@ -0,0 +1,263 @@
+/***********************************************************************
+* simple-tokio/src/telecode.rs
+*   Translation between Unicode and the Elliott 5-hole and 8-hole
+*   paper tape and teleprinter codes.
+* Copyright (C) 2026, Paul Kimpel.
+* Licensed under the MIT License, see
+*       http://www.opensource.org/licenses/mit-license.php
+************************************************************************
+* 5-hole telecode has 32 codes and two shift states. Codes 1-26 have
+* one meaning in letter shift and another in figure shift; 27 selects
+* figure shift and 31 letter shift; 28-30 are space, carriage return
+* and line feed in either shift. Blank tape (0) is ignored, and since
+* 31 has all holes punched, it also serves as erase.
+*
+* 8-hole code is the ISO 7-bit code with the pound sign in place of
+* the number sign, and an even-parity hole in channel 8. Blank tape (0)
+* and erase (all holes) are ignored.
+************************************************************************
+* Modification log.
+* 2026-10-16  agent
+*   Original version.
+*   Add tests of the shift, parity and unmappable-character handling.
+***********************************************************************/
+
+#![allow(dead_code)]
+
+use std::fmt;
+
+pub const BLANK: u8 = 0;
+
+// 5-hole telecode
+pub const FIGURE_SHIFT: u8 = 27;
+pub const SPACE: u8 = 28;
+pub const CARRIAGE_RETURN: u8 = 29;
+pub const LINE_FEED: u8 = 30;
+pub const LETTER_SHIFT: u8 = 31;
+pub const ERASE_5: u8 = 31;
+
+const LETTERS: [char; 27] = ['\0',
+        'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M',
+        'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'];
+const FIGURES: [char; 27] = ['\0',
+        '1', '2', '*', '4', '$', '=', '7', '8', '\'', ',', '+', ':', '-',
+        '.', '%', '0', '(', ')', '3', '?', '5', '6', '/', '@', '9', '£'];
+
+// 8-hole code
+pub const ERASE_8: u8 = 0xFF;
+const PARITY_8: u8 = 0x80;
+const POUND_8: u8 = 0x23;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TelecodeError {
+    Parity(u8),                         // 8-hole frame with odd parity
+    Unrepresentable(char)               // no code for this character
+}
+
+impl fmt::Display for TelecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TelecodeError::Parity(frame) => write!(f, "parity error in frame {:03o}", frame),
+            TelecodeError::Unrepresentable(c) => write!(f, "no telecode for {:?}", c)
+        }
+    }
+}
+
+impl std::error::Error for TelecodeError {}
+
+
+/***** 5-hole telecode *****/
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Decoder5 {
+    pub figures: bool                   // current shift state
+}
+
+impl Decoder5 {
+
+    pub fn new() -> Self {
+        Decoder5 {figures: false}
+    }
+
+    pub fn decode(&mut self, code: u8) -> Option<char> {
+        /* Translates one 5-hole code, updating the shift state. Returns None
+        for blank, erase and the shift codes themselves */
+
+        match code & 0o37 {
+            BLANK => None,
+            FIGURE_SHIFT => {
+                self.figures = true;
+                None
+            }
+            LETTER_SHIFT => {
+                self.figures = false;
+                None
+            }
+            SPACE => Some(' '),
+            CARRIAGE_RETURN => Some('\r'),
+            LINE_FEED => Some('\n'),
+            code => {
+                let table = if self.figures {&FIGURES} else {&LETTERS};
+                Some(table[code as usize])
+            }
+        }
+    }
+
+    pub fn decode_all(&mut self, codes: &[u8]) -> String {
+        codes.iter().filter_map(|&code| self.decode(code)).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Encoder5 {
+    pub figures: bool                   // current shift state
+}
+
+impl Encoder5 {
+
+    pub fn new() -> Self {
+        Encoder5 {figures: false}
+    }
+
+    pub fn encode(&mut self, c: char, codes: &mut Vec<u8>) -> Result<(), TelecodeError> {
+        /* Appends the code for c to codes, preceded by a shift code if the
+        shift state must change. Lower-case letters are folded to upper */
+
+        match c {
+            ' ' => codes.push(SPACE),
+            '\r' => codes.push(CARRIAGE_RETURN),
+            '\n' => codes.push(LINE_FEED),
+            _ => {
+                let c = c.to_ascii_uppercase();
+                if let Some(x) = LETTERS.iter().skip(1).position(|&t| t == c) {
+                    if self.figures {
+                        codes.push(LETTER_SHIFT);
+                        self.figures = false;
+                    }
+                    codes.push(x as u8 + 1);
+                } else if let Some(x) = FIGURES.iter().skip(1).position(|&t| t == c) {
+                    if !self.figures {
+                        codes.push(FIGURE_SHIFT);
+                        self.figures = true;
+                    }
+                    codes.push(x as u8 + 1);
+                } else {
+                    return Err(TelecodeError::Unrepresentable(c));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn encode_all(&mut self, text: &str) -> Result<Vec<u8>, TelecodeError> {
+        /* Encodes text, translating a newline as carriage return, line feed */
+
+        let mut codes = Vec::with_capacity(text.len());
+        for c in text.chars() {
+            if c == '\n' {
+                codes.push(CARRIAGE_RETURN);
+            }
+            self.encode(c, &mut codes)?;
+        }
+
+        Ok(codes)
+    }
+}
+
+
+/***** 8-hole code *****/
+
+fn with_parity(code: u8) -> u8 {
+    if code.count_ones() & 1 == 0 {code} else {code | PARITY_8}
+}
+
+pub fn decode8(frame: u8) -> Result<Option<char>, TelecodeError> {
+    /* Translates one 8-hole frame after checking its parity. Returns None
+    for blank and erase */
+
+    if frame == BLANK || frame == ERASE_8 {
+        Ok(None)
+    } else if frame.count_ones() & 1 != 0 {
+        Err(TelecodeError::Parity(frame))
+    } else {
+        match frame & !PARITY_8 {
+            POUND_8 => Ok(Some('£')),
+            code => Ok(Some(code as char))
+        }
+    }
+}
+
+pub fn encode8(c: char) -> Result<u8, TelecodeError> {
+    /* Returns the 8-hole frame for c, including its parity hole */
+
+    match c {
+        '£' => Ok(with_parity(POUND_8)),
+        '#' => Err(TelecodeError::Unrepresentable(c)),
+        c if (c as u32) > 0 && (c as u32) < 0x7F => Ok(with_parity(c as u8)),
+        _ => Err(TelecodeError::Unrepresentable(c))
+    }
+}
+
+pub fn decode8_all(frames: &[u8]) -> Result<String, TelecodeError> {
+    let mut text = String::with_capacity(frames.len());
+    for &frame in frames {
+        if let Some(c) = decode8(frame)? {
+            text.push(c);
+        }
+    }
+
+    Ok(text)
+}
+
+pub fn encode8_all(text: &str) -> Result<Vec<u8>, TelecodeError> {
+    text.chars().map(encode8).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn five_hole_shifts() {
+        let codes = Encoder5::new().encode_all("Ab12 CD£\n").unwrap();
+        assert_eq!(codes, [1, 2, FIGURE_SHIFT, 1, 2, SPACE, LETTER_SHIFT, 3, 4,
+                FIGURE_SHIFT, 26, CARRIAGE_RETURN, LINE_FEED]);
+
+        // Blank tape anywhere, and erase while in letter shift, read as nothing
+        let mut tape = vec![BLANK, ERASE_5];
+        for &code in codes.iter() {
+            tape.extend_from_slice(&[code, BLANK]);
+        }
+        let mut decoder = Decoder5::new();
+        assert_eq!(decoder.decode_all(&tape), "AB12 CD£\r\n");
+        assert!(decoder.figures);
+        assert_eq!(decoder.decode(ERASE_5), None);
+        assert!(!decoder.figures);
+    }
+
+    #[test]
+    fn eight_hole_parity() {
+        assert_eq!(encode8('A'), Ok(0x41));             // two holes, already even
+        assert_eq!(encode8('C'), Ok(0xC3));             // three holes, parity added
+        assert_eq!(encode8('£'), Ok(0xA3));
+        assert_eq!(encode8_all("CA£"), Ok(vec![0xC3, 0x41, 0xA3]));
+
+        assert_eq!(decode8_all(&[BLANK, 0xC3, ERASE_8, 0x41, 0xA3]), Ok("CA£".to_string()));
+        assert_eq!(decode8(0x43), Err(TelecodeError::Parity(0x43)));
+        assert_eq!(decode8_all(&[0x41, 0xC2]), Err(TelecodeError::Parity(0xC2)));
+    }
+
+    #[test]
+    fn unmappable_characters() {
+        let mut encoder = Encoder5::new();
+        assert_eq!(encoder.encode_all("A!"), Err(TelecodeError::Unrepresentable('!')));
+        assert_eq!(encoder.encode_all("\t"), Err(TelecodeError::Unrepresentable('\t')));
+
+        assert_eq!(encode8('#'), Err(TelecodeError::Unrepresentable('#')));
+        assert_eq!(encode8('\0'), Err(TelecodeError::Unrepresentable('\0')));
+        assert_eq!(encode8('é'), Err(TelecodeError::Unrepresentable('é')));
+        assert_eq!(encode8_all("ab#"), Err(TelecodeError::Unrepresentable('#')));
+    }
+}